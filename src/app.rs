@@ -1,45 +1,281 @@
 mod account;
+mod backend;
+mod category;
+mod category_manager;
+mod command_palette;
+mod credentials;
+mod error;
+mod layout;
+mod login_form;
 mod message;
 mod nav_pane;
 mod paprika;
+mod paths;
+mod photo_cache;
+mod profile;
+mod profile_form;
+mod recipe;
 mod recipe_button;
-mod recipe_fetcher;
+mod recipe_card;
+mod recipe_db;
+mod recipe_editor;
+mod recipe_filter;
+mod recipe_merge;
+mod recipe_sort;
+mod recipe_sync;
+mod settings;
+mod settings_form;
+mod shopping_list;
+mod shortcuts;
 mod simple_button;
 mod style;
+mod theme;
+mod trash;
 
-use chrono::Utc;
+use category::Category;
+use category_manager::CategoryManager;
+use command_palette::{CommandPalette, PaletteEntry, PaletteMessage};
+use error::Error;
+use layout::{Layout, SavedPane};
+use login_form::{LoginForm, LoginRequest};
 use message::Message;
 use nav_pane::NavPane;
+use paths::Paths;
+use photo_cache::Photo;
+use profile::Profile;
+use profile_form::ProfileForm;
+use recipe::RecipeView;
 use recipe_button::RecipeButton;
-use recipe_fetcher::RecipeFetcher;
+use recipe_card::{ListMode, RecipeCard};
+use recipe_db::{RecipeDb, SyncState};
+use recipe_editor::RecipeEditor;
+use recipe_filter::{CategoryChoice, FilterMessage, RecipeFilter, SearchIndex};
+use recipe_merge::RecipeMerge;
+use recipe_sort::{SortKey, SortOrder};
+use recipe_sync::{DownloadLimit, RecipeSync, SyncEvent, SyncProgress};
+use settings::Settings;
+use settings_form::SettingsForm;
+use shopping_list::ShoppingList;
+use shortcuts::Action;
 use simple_button::SimpleButton;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
-
-use edit;
+use std::time;
+use theme::{NamedTheme, Theme};
+use tokio::sync::Notify;
+use trash::TrashView;
 
 use iced::{
-    executor,
+    button, executor, keyboard,
     pane_grid::{self, Axis},
-    scrollable, Align, Application, Clipboard, Column, Command, Container, Element, Length,
-    PaneGrid, Scrollable, Subscription,
+    pick_list, scrollable, Align, Application, Button, Clipboard, Column, Command, Container,
+    Element, Length, PaneGrid, PickList, Row, Scrollable, Size, Subscription, Text,
 };
+use iced_native::{event, subscription, window, Event};
+
+/// The smallest window worth restoring
+const MIN_WIDTH: u32 = 400;
+const MIN_HEIGHT: u32 = 300;
+/// What the recipe grid can't use of its pane's width: the padding, border and scrollbar
+const GRID_MARGIN: f32 = 30.0;
+
+/// The window the size it was left at, read before the app starts. Anything wrong with the
+/// settings is reported once the app is up.
+pub fn window_settings() -> iced::window::Settings {
+    let window = paths::config_dir()
+        .and_then(|dir| Settings::load(&paths::settings_file(&dir)))
+        .map(|settings| settings.window)
+        .unwrap_or_default();
+    iced::window::Settings {
+        size: (window.width.max(MIN_WIDTH), window.height.max(MIN_HEIGHT)),
+        ..Default::default()
+    }
+}
 
 pub struct HomePage {
     panes: pane_grid::State<Pane>,
-    paprika: Arc<Mutex<paprika::Paprika>>,
+    focus: Option<pane_grid::Pane>,
+    /// The recipe pane focused last, where clicked recipes open
+    last_detail: Option<pane_grid::Pane>,
+    /// Set once the window has been asked to close and the layout is saved
+    exiting: bool,
+    /// Saving on close failed and the user has been told, so closing again quits regardless
+    close_failed: bool,
+    palette: Option<CommandPalette>,
+    paprika: paprika::SharedPaprika,
     recipes: Arc<Mutex<Vec<paprika_api::api::Recipe>>>,
+    db: Arc<Mutex<RecipeDb>>,
+    /// Uids of recipes edited here that haven't been uploaded yet
+    unsynced: HashSet<String>,
+    /// Uids of recipes whose local edits clash with the server's and need merging
+    conflicts: HashSet<String>,
+    sync: SyncProgress,
+    /// Bumped whenever the account or profile changes, which restarts the sync engine
+    sync_session: u64,
+    /// Asks the sync engine for a pass outside its schedule
+    sync_wake: Arc<Notify>,
+    logged_in: bool,
+    /// Keeps the sync engine stopped while a logout clears the local data
+    logging_out: bool,
+    categories: Vec<Category>,
+    /// The category picked in the nav pane, if any
+    selected_category: Option<String>,
+    /// Downloaded photos, by `photo_cache::photo_key`
+    photos: HashMap<String, Photo>,
+    /// Photos being downloaded, or that failed to download this session
+    photo_downloads: HashSet<String>,
+    /// Keeps photo downloads within the sync settings' limits
+    photo_limit: Arc<DownloadLimit>,
+    /// When each recipe was last seen to change, for sorting by recent edits
+    edited: HashMap<String, time::SystemTime>,
+    /// The recipes' text, folded for searching
+    search_index: SearchIndex,
+    /// How much space downloaded photos take, in bytes
+    photo_bytes: u64,
+    settings: Settings,
+    /// The built-in themes and the user's own
+    themes: Vec<NamedTheme>,
+    /// Where every profile's files are kept
+    root_paths: Paths,
+    /// Where the current profile's files are kept
+    paths: Paths,
+    status: Option<Error>,
+    dismiss_status: button::State,
+    /// Waits for the user to agree to lose this many recipes' changes that haven't been
+    /// uploaded
+    pending_discard: Option<(PendingDiscard, Losses)>,
+    confirm_discard: button::State,
+    cancel_discard: button::State,
+}
+
+/// Something that clears the local data or closes the recipe panes, and so loses changes that
+/// haven't been uploaded or saved
+#[derive(Debug, Clone, PartialEq)]
+enum PendingDiscard {
+    Logout,
+    /// Saving settings from the pane that point the profile at another backend
+    ChangeBackend(pane_grid::Pane, Box<Settings>),
+    /// Picking another profile, by its id
+    SwitchProfile(String),
+}
+
+/// What a `PendingDiscard` would lose
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Losses {
+    /// Recipes with edits, deletions or conflicts that haven't made it to the server
+    unuploaded: usize,
+    /// Editor and merge panes with changes that haven't been saved
+    unsaved: usize,
+}
+
+impl Losses {
+    fn is_empty(&self) -> bool {
+        self.unuploaded == 0 && self.unsaved == 0
+    }
+}
+
+impl PendingDiscard {
+    fn question(&self, losses: Losses) -> String {
+        let mut changes = Vec::new();
+        match losses.unuploaded {
+            0 => (),
+            1 => changes.push("1 recipe has changes that haven't been uploaded".to_string()),
+            n => changes.push(format!(
+                "{} recipes have changes that haven't been uploaded",
+                n
+            )),
+        }
+        match losses.unsaved {
+            0 => (),
+            1 => changes.push("1 open editor has unsaved changes".to_string()),
+            n => changes.push(format!("{} open editors have unsaved changes", n)),
+        }
+        let consequence = match self {
+            PendingDiscard::Logout => "Logging out deletes them from this device.",
+            PendingDiscard::ChangeBackend(..) => {
+                "Syncing this profile somewhere else deletes them from this device."
+            }
+            PendingDiscard::SwitchProfile(_) => "Switching profiles closes them without saving.",
+        };
+        format!("{}. {}", changes.join(", and "), consequence)
+    }
+
+    fn confirm_label(&self) -> &'static str {
+        match self {
+            PendingDiscard::Logout => "Log out anyway",
+            PendingDiscard::ChangeBackend(..) => "Change anyway",
+            PendingDiscard::SwitchProfile(_) => "Switch anyway",
+        }
+    }
+}
+
+/// What a saved recipe started out as, to tell whether the server copy has moved on since
+enum EditedFrom {
+    /// The recipe as it was when the edit started
+    Opened(paprika_api::api::Recipe),
+    /// The server copy it was merged with
+    Merge(paprika_api::api::Recipe),
 }
 
 struct Pane {
-    pub content: Content,
-    pub is_nav_pane: bool,
+    pub kind: PaneKind,
+    controls: Controls,
+}
+
+/// The buttons in a pane's title bar
+struct Controls {
+    split_right: button::State,
+    split_down: button::State,
+    close: button::State,
+}
+
+/// App state that panes need to draw themselves
+struct PaneContext<'a> {
+    #[allow(unused)]
+    total_panes: usize,
+    sort: SortOrder,
+    categories: &'a [Category],
+    selected_category: Option<&'a str>,
+    photos: &'a HashMap<String, Photo>,
+    unsynced: &'a HashSet<String>,
+    conflicts: &'a HashSet<String>,
+    /// How many recipes are in the trash
+    trashed: usize,
+    sync: SyncProgress,
+    logged_in: bool,
+    profiles: &'a [Profile],
+    profile: Profile,
+    settings: &'a Settings,
+    theme: Theme,
+    /// How wide each pane is, so the recipe grid can fit its cards
+    pane_widths: HashMap<pane_grid::Pane, f32>,
+    search_index: &'a SearchIndex,
+}
+
+#[allow(clippy::large_enum_variant)]
+enum PaneKind {
+    Nav(NavPane),
+    List(Content),
+    Detail(RecipeView),
+    Editor(RecipeEditor),
+    Categories(CategoryManager),
+    Merge(RecipeMerge),
+    Login(LoginForm),
+    Profile(ProfileForm),
+    Settings(SettingsForm),
+    Trash(TrashView),
+    ShoppingList(ShoppingList),
 }
 
 struct Content {
     scroll: scrollable::State,
-    nav_pane: NavPane,
     recipes: Arc<Mutex<Vec<paprika_api::api::Recipe>>>,
     recipe_buttons: Vec<RecipeButton>,
+    recipe_cards: Vec<RecipeCard>,
+    filter: RecipeFilter,
+    sort_picker: pick_list::State<SortKey>,
+    sort_direction: button::State,
+    list_mode: button::State,
 }
 
 impl Application for HomePage {
@@ -48,131 +284,771 @@ impl Application for HomePage {
     type Flags = ();
 
     fn new(_flags: ()) -> (Self, Command<Message>) {
-        let paprika = paprika::Paprika::new();
-        let mutex = std::sync::Mutex::new(paprika);
-        let arc = std::sync::Arc::new(mutex);
-
         let recipes =
             std::sync::Arc::new(std::sync::Mutex::new(Vec::<paprika_api::api::Recipe>::new()));
 
-        // create the State<Pane>, then split it
-        let (mut panes, pane) = pane_grid::State::new(Pane::new(true, recipes.clone()));
-        let (_split_panes, _split) = panes
-            .split(Axis::Vertical, &pane, Pane::new(false, recipes.clone()))
-            .expect("Failed to split panes");
+        // the saved layout replaces this once the recipes it shows are loaded
+        let (panes, _) = pane_grid::State::new(Pane::new(PaneKind::Nav(NavPane::new())));
+
+        // settings can move the cache, so they're found first
+        let mut status = None;
+        let config_dir = paths::config_dir().unwrap_or_else(|e| {
+            status = Some(e);
+            Paths::legacy().config_dir
+        });
+        let settings_file = paths::settings_file(&config_dir);
+        let mut settings = Settings::load(&settings_file).unwrap_or_else(|e| {
+            // keep the file for the user to fix, rather than saving the defaults over it
+            status = Some(match (e, Settings::set_aside(&settings_file)) {
+                (Error::Settings(details), Ok(backup)) => Error::Settings(format!(
+                    "{}. It was moved to {} and the defaults are in use",
+                    details,
+                    backup.display()
+                )),
+                (e, _) => e,
+            });
+            Settings::default()
+        });
+        if let Some(problem) = settings.shortcuts.problems.first() {
+            status = Some(Error::Settings(problem.clone()));
+        }
+        if settings.profiles.is_empty() {
+            settings.profiles.push(Profile::default_profile());
+        }
+        let themes = theme::load(&theme::themes_file(&config_dir)).unwrap_or_else(|e| {
+            status = Some(e);
+            theme::built_in()
+        });
+        let root_paths =
+            Paths::resolve(config_dir, settings.cache_dir.as_deref()).unwrap_or_else(|e| {
+                status = Some(e);
+                Paths::legacy()
+            });
+        if let Err(e) = paths::migrate_legacy_cache(&root_paths.cache_dir) {
+            status = Some(e);
+        }
 
-        panes.resize(&_split, 0.15);
+        // `open_profile` fills in everything that belongs to a profile
+        let mut home_page = HomePage {
+            panes,
+            focus: None,
+            last_detail: None,
+            exiting: false,
+            close_failed: false,
+            palette: None,
+            paprika: Arc::new(tokio::sync::Mutex::new(paprika::Paprika::with_backend(
+                settings.current_profile().backend.build(),
+            ))),
+            recipes,
+            db: Arc::new(Mutex::new(
+                RecipeDb::open_in_memory().expect("Couldn't create an in-memory recipe database"),
+            )),
+            unsynced: HashSet::new(),
+            conflicts: HashSet::new(),
+            sync: SyncProgress::default(),
+            sync_session: 0,
+            sync_wake: Arc::new(Notify::new()),
+            logged_in: false,
+            logging_out: false,
+            categories: Vec::new(),
+            selected_category: None,
+            photos: HashMap::new(),
+            photo_downloads: HashSet::new(),
+            photo_limit: Arc::new(DownloadLimit::new(settings.sync)),
+            edited: HashMap::new(),
+            search_index: SearchIndex::default(),
+            photo_bytes: 0,
+            settings,
+            themes,
+            paths: root_paths.clone(),
+            root_paths,
+            status,
+            dismiss_status: button::State::new(),
+            pending_discard: None,
+            confirm_discard: button::State::new(),
+            cancel_discard: button::State::new(),
+        };
+        let command = home_page.open_profile();
+        home_page.restore_layout();
+        home_page.offer_unlock();
 
-        (
-            HomePage {
-                panes: panes,
-                paprika: arc.clone(),
-                recipes: recipes.clone(),
-            },
-            Command::none(),
-        )
+        (home_page, command)
     }
 
     fn update(&mut self, message: Message, _clipboard: &mut Clipboard) -> Command<Message> {
         match message {
             Message::Split(axis, pane) => {
-                let _result = self
-                    .panes
-                    .split(axis, &pane, Pane::new(false, self.recipes.clone()));
+                // a recipe splits into a second copy to browse from; anything else into a list
+                let kind = match self.panes.get(&pane).map(|pane| &pane.kind) {
+                    Some(PaneKind::Detail(detail)) => {
+                        PaneKind::Detail(RecipeView::new(detail.recipe.clone()))
+                    }
+                    Some(_) => PaneKind::List(Content::new(self.recipes.clone())),
+                    None => return Command::none(),
+                };
+                if let Some((pane, _)) = self.panes.split(axis, &pane, Pane::new(kind)) {
+                    self.focus_pane(pane);
+                }
+            }
+            // an editor with changes asks before they're thrown away
+            Message::Close(pane) | Message::EditorCancelled(pane) => {
+                if let Some(PaneKind::Editor(editor)) =
+                    self.panes.get_mut(&pane).map(|pane| &mut pane.kind)
+                {
+                    if editor.has_changes() {
+                        editor.confirming_discard = true;
+                        return Command::none();
+                    }
+                }
+                self.close_pane(pane);
+            }
+            Message::EditorDiscarded(pane) => self.close_pane(pane),
+            Message::Clicked(pane) => self.focus_pane(pane),
+            Message::Dragged(pane_grid::DragEvent::Dropped { pane, target }) => {
+                self.panes.swap(&pane, &target)
+            }
+            Message::Dragged(_) => (),
+            Message::Resized(pane_grid::ResizeEvent { split, ratio }) => {
+                self.panes.resize(&split, ratio)
+            }
+            Message::CloseRequested => {
+                let layout = self.save_layout();
+                let settings = self.settings.save(&self.root_paths.settings_file());
+                match layout.and(settings) {
+                    Err(e) if !self.close_failed => {
+                        self.status = Some(e);
+                        self.close_failed = true;
+                    }
+                    _ => self.exiting = true,
+                }
+            }
+            Message::WindowResized(width, height) => {
+                self.settings.window.width = width;
+                self.settings.window.height = height;
+            }
+            Message::KeyPressed(key, modifiers) => {
+                if let Some(palette) = &mut self.palette {
+                    let results = palette
+                        .results(&self.recipes.lock().unwrap(), &self.settings.shortcuts)
+                        .len();
+                    match key {
+                        keyboard::KeyCode::Escape => self.palette = None,
+                        keyboard::KeyCode::Up => palette.select(-1, results),
+                        keyboard::KeyCode::Down => palette.select(1, results),
+                        _ => (),
+                    }
+                }
+                if let Some(action) = self.settings.shortcuts.action(key, modifiers) {
+                    return self.run_action(action, _clipboard);
+                }
+            }
+            Message::CommandPalette(PaletteMessage::Submitted) => {
+                let entry = self.palette.as_ref().and_then(|palette| {
+                    let results =
+                        palette.results(&self.recipes.lock().unwrap(), &self.settings.shortcuts);
+                    palette.selected(&results).cloned()
+                });
+                if let Some(entry) = entry {
+                    return self.update(Message::PaletteEntryPicked(entry), _clipboard);
+                }
+            }
+            Message::CommandPalette(palette_message) => {
+                if let Some(palette) = &mut self.palette {
+                    palette.update(palette_message);
+                }
+            }
+            Message::PaletteEntryPicked(entry) => {
+                self.palette = None;
+                match entry {
+                    PaletteEntry::Action(action) => return self.run_action(action, _clipboard),
+                    PaletteEntry::Recipe(uid) => {
+                        return self.update(Message::RecipeClicked(uid), _clipboard)
+                    }
+                }
             }
-            Message::Close(_) => todo!(),
 
             Message::NewRecipeClicked => {
                 println!("New recipe!");
-                let mut recipe = paprika_api::api::Recipe::default();
-
-                recipe.created = Utc::now().format("%Y-%m-%d %H:%M:%S".into()).to_string();
-
-                let serialized = serde_json::to_string_pretty(&recipe).unwrap();
-
-                let edited = edit::edit(serialized).unwrap();
-
-                let mut edited_recipe: paprika_api::api::Recipe =
-                    serde_json::from_str(&edited).unwrap();
-
-                {
-                    let _paprika = self.paprika.clone();
-                    std::thread::spawn(move || {
-                        tokio::runtime::Builder::new_current_thread()
-                            .enable_all()
-                            .build()
-                            .unwrap()
-                            .block_on(_paprika.lock().unwrap().update_recipe(&mut edited_recipe));
-                    });
-                }
+                self.open_editor(RecipeEditor::new_recipe(
+                    self.categories.clone(),
+                    &self.settings.default_servings,
+                ));
             }
             Message::RecipeClicked(recipe_uid) => {
                 println!("Recipe clicked: {:?}", recipe_uid);
-                let mut recipes = self.recipes.lock().unwrap();
+                if let Some(recipe) = self.find_recipe(&recipe_uid) {
+                    self.show_recipe(recipe);
+                }
+            }
+            Message::EditRecipeClicked(recipe_uid) => {
+                let already_open = self.panes.iter().any(|(_, pane)| match &pane.kind {
+                    PaneKind::Editor(editor) => editor.uid() == recipe_uid,
+                    _ => false,
+                });
+                if already_open {
+                    return Command::none();
+                }
 
-                let found_recipe = recipes.iter_mut().find(|_recipe| _recipe.uid == recipe_uid);
-                let serialized = serde_json::to_string_pretty(found_recipe.unwrap()).unwrap();
+                if let Some(recipe) = self.find_recipe(&recipe_uid) {
+                    self.open_editor(RecipeEditor::new(recipe, self.categories.clone()));
+                }
+            }
+            Message::Editor(pane, editor_message) => {
+                if let Some(PaneKind::Editor(editor)) =
+                    self.panes.get_mut(&pane).map(|pane| &mut pane.kind)
+                {
+                    editor.update(editor_message);
+                }
+            }
+            Message::EditorSaved(pane) => {
+                let (recipe, opened) = match self.panes.get_mut(&pane).map(|pane| &mut pane.kind) {
+                    Some(PaneKind::Editor(editor)) => {
+                        editor.saving = true;
+                        (editor.to_recipe(), editor.opened().clone())
+                    }
+                    _ => return Command::none(),
+                };
+                return self.save_recipe(pane, recipe, EditedFrom::Opened(opened));
+            }
+            Message::TrashRecipeClicked(pane, uid) => {
+                return self.change_recipe(pane, &uid, |recipe| recipe.in_trash = true)
+            }
+            Message::RestoreRecipeClicked(pane, uid) => {
+                return self.change_recipe(pane, &uid, |recipe| recipe.in_trash = false)
+            }
+            Message::ShoppingListToggled(pane, uid) => {
+                return self.change_recipe(pane, &uid, |recipe| {
+                    recipe.on_grocery_list = !recipe.on_grocery_list
+                })
+            }
+            Message::ShoppingListClicked => {
+                let already_open = self
+                    .panes
+                    .iter()
+                    .any(|(_, pane)| matches!(pane.kind, PaneKind::ShoppingList(_)));
+                if !already_open {
+                    self.split_list_pane(PaneKind::ShoppingList(ShoppingList::new(
+                        self.recipes.clone(),
+                    )));
+                }
+            }
+            Message::TrashClicked => {
+                let already_open = self
+                    .panes
+                    .iter()
+                    .any(|(_, pane)| matches!(pane.kind, PaneKind::Trash(_)));
+                if !already_open {
+                    self.split_list_pane(PaneKind::Trash(TrashView::new(self.recipes.clone())));
+                }
+            }
+            Message::Trash(pane, trash_message) => {
+                if let Some(PaneKind::Trash(trash)) =
+                    self.panes.get_mut(&pane).map(|pane| &mut pane.kind)
+                {
+                    trash.update(trash_message);
+                }
+            }
+            Message::TrashDeleteConfirmed(pane) => {
+                let doomed = match self.panes.get_mut(&pane).map(|pane| &mut pane.kind) {
+                    Some(PaneKind::Trash(trash)) => trash.confirm(),
+                    _ => return Command::none(),
+                };
+                self.delete_for_good(doomed);
+            }
+            Message::Filter(pane, filter_message) => {
+                if let Some(PaneKind::List(content)) =
+                    self.panes.get_mut(&pane).map(|pane| &mut pane.kind)
+                {
+                    content.filter.update(filter_message);
+                }
+            }
+            Message::RecipeSaved(pane, result) => match result {
+                Ok((recipe, upload_error)) => {
+                    self.edited
+                        .insert(recipe.uid.clone(), time::SystemTime::now());
+                    self.refresh_unsynced();
+                    // the editor has done its job once its recipe is safely stored
+                    if let Some(PaneKind::Editor(_)) | Some(PaneKind::Merge(_)) =
+                        self.panes.get(&pane).map(|pane| &pane.kind)
+                    {
+                        self.close_pane(pane);
+                    }
+                    match upload_error {
+                        // being offline is expected; the nav pane shows what's waiting
+                        Some(Error::Network(_)) => (),
+                        Some(Error::Conflict(_)) => self.open_merge(&recipe.uid),
+                        Some(e) => self.status = Some(e),
+                        None => (),
+                    }
+                    return self.update(Message::RecipeFetched(Some(recipe)), _clipboard);
+                }
+                Err(e) => {
+                    match self.panes.get_mut(&pane).map(|pane| &mut pane.kind) {
+                        Some(PaneKind::Editor(editor)) => editor.saving = false,
+                        Some(PaneKind::Merge(merge)) => merge.saving = false,
+                        _ => (),
+                    }
+                    self.status = Some(e);
+                }
+            },
+            Message::ResolveConflictClicked(uid) => self.open_merge(&uid),
+            Message::Merge(pane, merge_message) => {
+                if let Some(PaneKind::Merge(merge)) =
+                    self.panes.get_mut(&pane).map(|pane| &mut pane.kind)
+                {
+                    merge.update(merge_message);
+                }
+            }
+            Message::MergeSaved(pane) => {
+                let (merged, theirs) = match self.panes.get_mut(&pane).map(|pane| &mut pane.kind) {
+                    Some(PaneKind::Merge(merge)) => {
+                        merge.saving = true;
+                        (merge.to_recipe(), merge.theirs.clone())
+                    }
+                    _ => return Command::none(),
+                };
+                return self.save_recipe(pane, merged, EditedFrom::Merge(theirs));
+            }
+            Message::OpenUrl(url) => {
+                if let Err(e) = open::that(&url) {
+                    self.status = Some(Error::Launch(format!("{}: {}", url, e)));
+                }
+            }
+            Message::RecipeFetched(recipe) => {
+                if let Some(recipe) = recipe {
+                    let fetch_photo = self.fetch_photo(&recipe);
 
-                let edited = edit::edit(serialized).unwrap();
+                    for (_, pane) in self.panes.iter_mut() {
+                        if let PaneKind::Detail(detail) = &mut pane.kind {
+                            if detail.recipe.uid == recipe.uid {
+                                detail.recipe = recipe.clone();
+                            }
+                        }
+                    }
 
-                let mut edited_recipe: paprika_api::api::Recipe =
-                    serde_json::from_str(&edited).unwrap();
+                    let seen = self
+                        .db
+                        .lock()
+                        .unwrap()
+                        .updated_at(&recipe.uid)
+                        .ok()
+                        .flatten()
+                        .unwrap_or_else(time::SystemTime::now);
+                    let edited = self.edited.entry(recipe.uid.clone()).or_insert(seen);
+                    *edited = (*edited).max(seen);
 
-                {
-                    let _paprika = self.paprika.clone();
-                    std::thread::spawn(move || {
-                        tokio::runtime::Builder::new_current_thread()
-                            .enable_all()
-                            .build()
-                            .unwrap()
-                            .block_on(_paprika.lock().unwrap().update_recipe(&mut edited_recipe));
-                    });
-                }
-            }
-            Message::RecipeFetched(recipe) => match recipe {
-                Some(recipe) => {
                     let mut recipes = self.recipes.lock().unwrap();
                     let found_recipe = recipes.iter_mut().find(|_recipe| _recipe.uid == recipe.uid);
                     match found_recipe {
                         Some(_recipe) => *_recipe = recipe,
                         None => recipes.push(recipe),
                     }
+                    self.settings.sort.sort(&mut recipes, &self.edited);
+                    return fetch_photo;
+                }
+            }
+            Message::Sync(event) => {
+                self.sync.record(&event);
+                if !matches!(event, SyncEvent::Fetched(_) | SyncEvent::Started(_)) {
+                    self.refresh_unsynced();
+                }
+                match event {
+                    SyncEvent::Fetched(recipe) | SyncEvent::Uploaded(recipe) => {
+                        return self.update(Message::RecipeFetched(Some(recipe)), _clipboard)
+                    }
+                    SyncEvent::Removed(uid) | SyncEvent::DeleteUploaded(uid) => {
+                        self.recipe_removed(&uid)
+                    }
+                    // the database failing to store something isn't fixed by the next pass
+                    SyncEvent::RecipeFailed(e @ Error::Database(_)) => self.status = Some(e),
+                    // anything else is retried, and the recipe stays listed as waiting meanwhile
+                    SyncEvent::RecipeFailed(_) => (),
+                    // the nav pane already says we're offline
+                    SyncEvent::Failed(Error::Network(_)) => (),
+                    SyncEvent::Failed(Error::LoggedOut) => self.logged_in = false,
+                    SyncEvent::Failed(e) => self.status = Some(e),
+                    SyncEvent::Conflicted(uid) => {
+                        if let Some(recipe) = self.find_recipe(&uid) {
+                            self.status = Some(Error::Conflict(recipe.name));
+                        }
+                    }
+                    SyncEvent::Started(_) => self.logged_in = true,
+                    SyncEvent::Skipped | SyncEvent::Finished => (),
+                }
+            }
+            Message::SyncNowClicked => self.sync_wake.notify_one(),
+            // a missing photo isn't worth interrupting anyone over; the placeholder stays
+            Message::PhotoFetched(key, result) => {
+                if let Ok(photo) = result {
+                    self.photo_downloads.remove(&key);
+                    self.photo_bytes += photo.size();
+                    self.photos.insert(key, photo);
+                }
+            }
+            Message::CategoriesFetched(Ok(categories)) => self.categories_changed(categories),
+            Message::CategoriesFetched(Err(e)) => self.status = Some(e),
+            Message::CategorySelected(uid) => {
+                let choice = uid
+                    .as_ref()
+                    .and_then(|uid| self.categories.iter().find(|c| &c.uid == uid))
+                    .map(|category| CategoryChoice::Only(category.clone(), 0))
+                    .unwrap_or(CategoryChoice::All);
+                for (_, pane) in self.panes.iter_mut() {
+                    if let PaneKind::List(content) = &mut pane.kind {
+                        content
+                            .filter
+                            .update(FilterMessage::CategoryChanged(choice.clone()));
+                    }
+                }
+                self.selected_category = uid;
+            }
+            Message::ManageCategoriesClicked => {
+                let already_open = self
+                    .panes
+                    .iter()
+                    .any(|(_, pane)| matches!(pane.kind, PaneKind::Categories(_)));
+                if !already_open {
+                    self.split_list_pane(PaneKind::Categories(CategoryManager::new(
+                        self.categories.clone(),
+                    )));
+                }
+            }
+            Message::CategoryManager(pane, category_message) => {
+                if let Some(PaneKind::Categories(manager)) =
+                    self.panes.get_mut(&pane).map(|pane| &mut pane.kind)
+                {
+                    manager.update(category_message);
+                }
+            }
+            Message::CategoriesSaveClicked(pane) => {
+                let (categories, deleted) =
+                    match self.panes.get_mut(&pane).map(|pane| &mut pane.kind) {
+                        Some(PaneKind::Categories(manager)) => {
+                            manager.saving = true;
+                            manager.changes()
+                        }
+                        _ => return Command::none(),
+                    };
+                return self.save_categories(pane, categories, deleted);
+            }
+            Message::CategoriesSaved(pane, result) => match result {
+                Ok((categories, recipes)) => {
+                    if let Some(PaneKind::Categories(_)) =
+                        self.panes.get(&pane).map(|pane| &pane.kind)
+                    {
+                        self.close_pane(pane);
+                    }
+                    self.categories_changed(categories);
+                    if recipes.is_empty() {
+                        return Command::none();
+                    }
+                    self.refresh_unsynced();
+                    self.sync_wake.notify_one();
+                    let commands = recipes
+                        .into_iter()
+                        .map(|recipe| {
+                            self.edited
+                                .insert(recipe.uid.clone(), time::SystemTime::now());
+                            self.update(Message::RecipeFetched(Some(recipe)), _clipboard)
+                        })
+                        .collect::<Vec<_>>();
+                    return Command::batch(commands);
+                }
+                Err(e) => {
+                    if let Some(PaneKind::Categories(manager)) =
+                        self.panes.get_mut(&pane).map(|pane| &mut pane.kind)
+                    {
+                        manager.saving = false;
+                    }
+                    self.status = Some(e);
                 }
-                None => {}
             },
+            Message::SortKeyChanged(key) => {
+                self.settings.sort.key = key;
+                self.sort_changed();
+            }
+            Message::SettingsClicked => {
+                let already_open = self
+                    .panes
+                    .iter()
+                    .any(|(_, pane)| matches!(pane.kind, PaneKind::Settings(_)));
+                if !already_open {
+                    self.split_list_pane(PaneKind::Settings(SettingsForm::new(
+                        &self.settings,
+                        &self.themes,
+                    )));
+                }
+            }
+            Message::SettingsForm(pane, settings_message) => {
+                if let Some(PaneKind::Settings(form)) =
+                    self.panes.get_mut(&pane).map(|pane| &mut pane.kind)
+                {
+                    form.update(settings_message);
+                }
+            }
+            Message::SettingsSaved(pane) => {
+                let changed = match self.panes.get(&pane).map(|pane| &pane.kind) {
+                    Some(PaneKind::Settings(form)) => form.to_settings(&self.settings),
+                    _ => return Command::none(),
+                };
+                if let Ok(changed) = changed {
+                    // a new backend starts the profile over, so ask before losing anything
+                    if changed.current_profile().backend != self.settings.current_profile().backend
+                    {
+                        match self.clearing_losses() {
+                            Ok(losses) if losses.is_empty() => (),
+                            Ok(losses) => {
+                                let discard =
+                                    PendingDiscard::ChangeBackend(pane, Box::new(changed));
+                                self.pending_discard = Some((discard, losses));
+                                return Command::none();
+                            }
+                            Err(e) => {
+                                self.status = Some(e);
+                                return Command::none();
+                            }
+                        }
+                    }
+                    self.close_pane(pane);
+                    return self.settings_changed(changed);
+                }
+            }
+            Message::SortDirectionToggled => {
+                self.settings.sort.descending = !self.settings.sort.descending;
+                self.sort_changed();
+            }
+            Message::ListModeToggled => {
+                self.settings.list_mode = self.settings.list_mode.toggled();
+                if let Err(e) = self.settings.save(&self.root_paths.settings_file()) {
+                    self.status = Some(e);
+                }
+            }
+            Message::ErrorOccurred(e) => self.status = Some(e),
+            Message::ErrorDismissed => self.status = None,
+            Message::ProfileSelected(id) => return self.select_profile(id),
+            Message::NewProfileClicked => {
+                let already_open = self
+                    .panes
+                    .iter()
+                    .any(|(_, pane)| matches!(pane.kind, PaneKind::Profile(_)));
+                if !already_open {
+                    self.split_list_pane(PaneKind::Profile(ProfileForm::new()));
+                }
+            }
+            Message::ProfileForm(pane, profile_message) => {
+                if let Some(PaneKind::Profile(form)) =
+                    self.panes.get_mut(&pane).map(|pane| &mut pane.kind)
+                {
+                    form.update(profile_message);
+                }
+            }
+            Message::ProfileCreated(pane) => {
+                let profile = match self.panes.get(&pane).map(|pane| &pane.kind) {
+                    Some(PaneKind::Profile(form)) => form.to_profile(),
+                    _ => None,
+                };
+                if let Some(profile) = profile {
+                    self.close_pane(pane);
+                    let id = profile.id.clone();
+                    self.settings.profiles.push(profile);
+                    if let Err(e) = self.settings.save(&self.root_paths.settings_file()) {
+                        self.status = Some(e);
+                    }
+                    return self.select_profile(id);
+                }
+            }
             Message::LoginClicked => {
-                let mut account = account::Account::new("".into(), "".into());
-
-                let serialized = serde_json::to_string_pretty(&account).unwrap();
-
-                let edited = edit::edit(serialized).unwrap();
-
-                account = serde_json::from_str(&edited).unwrap();
-                
+                let already_open = self
+                    .panes
+                    .iter()
+                    .any(|(_, pane)| matches!(pane.kind, PaneKind::Login(_)));
+                if !already_open {
+                    self.split_list_pane(PaneKind::Login(LoginForm::log_in()));
+                }
+            }
+            Message::Login(pane, login_message) => {
+                if let Some(PaneKind::Login(form)) =
+                    self.panes.get_mut(&pane).map(|pane| &mut pane.kind)
                 {
-                    let mut _paprika = self.paprika.lock().unwrap();
-                    _paprika.account = account;
-                    _paprika.token = "".into();
-                    _paprika.recipe_entries.clear();
-                    _paprika.last_fetched = 0;
+                    form.update(login_message);
                 }
+            }
+            Message::LoginSubmitted(pane) => {
+                let request = match self.panes.get_mut(&pane).map(|pane| &mut pane.kind) {
+                    Some(PaneKind::Login(form)) if !form.busy => form.submit(),
+                    _ => return Command::none(),
+                };
+                return self.log_in(pane, request);
+            }
+            Message::LoggedIn(pane, Ok(())) => {
+                if let Some(PaneKind::Login(_)) = self.panes.get(&pane).map(|pane| &pane.kind) {
+                    self.close_pane(pane);
+                }
+                self.logged_in = true;
+                self.restart_sync();
+                // with manual syncing, logging in is as good as asking for a sync
+                if self.settings.sync.interval().is_none() {
+                    self.sync_wake.notify_one();
+                }
+                return self.fetch_categories();
+            }
+            Message::LoggedIn(pane, Err(e)) => {
+                if let Some(PaneKind::Login(form)) =
+                    self.panes.get_mut(&pane).map(|pane| &mut pane.kind)
                 {
-                    let mut _recipes = self.recipes.lock().unwrap();
-                    _recipes.clear();
+                    form.busy = false;
+                }
+                self.status = Some(e);
+            }
+            Message::LogoutClicked => match self.clearing_losses() {
+                Ok(losses) if losses.is_empty() => return self.log_out(),
+                Ok(losses) => self.pending_discard = Some((PendingDiscard::Logout, losses)),
+                Err(e) => self.status = Some(e),
+            },
+            Message::DiscardConfirmed => match self.pending_discard.take() {
+                Some((PendingDiscard::Logout, _)) => return self.log_out(),
+                Some((PendingDiscard::ChangeBackend(pane, settings), _)) => {
+                    self.close_pane(pane);
+                    return self.settings_changed(*settings);
                 }
+                Some((PendingDiscard::SwitchProfile(id), _)) => return self.switch_profile(id),
+                None => (),
+            },
+            Message::DiscardCancelled => self.pending_discard = None,
+            Message::LoggedOut(result) => {
+                if let Err(e) = result {
+                    self.status = Some(e);
+                }
+                self.clear_local_data();
+                self.logged_in = false;
+                self.logging_out = false;
+                self.restart_sync();
             }
-            Message::AccountChanged(_, _) => todo!(),
         }
         Command::none()
     }
 
-    fn view(&mut self) -> Element<Message> {
+    fn view(&mut self) -> Element<'_, Message> {
+        let theme = self.theme();
+        // only recipes that changed since the last frame are folded again
+        self.search_index.update(&self.recipes.lock().unwrap());
+        let window = Size::new(
+            self.settings.window.width as f32,
+            self.settings.window.height as f32,
+        );
+        let pane_widths = self
+            .panes
+            .layout()
+            .pane_regions(0.0, window)
+            .into_iter()
+            .map(|(pane, region)| (pane, region.width))
+            .collect();
+        let context = PaneContext {
+            total_panes: self.panes.len(),
+            sort: self.settings.sort,
+            categories: &self.categories,
+            selected_category: self.selected_category.as_deref(),
+            photos: &self.photos,
+            unsynced: &self.unsynced,
+            conflicts: &self.conflicts,
+            trashed: self
+                .recipes
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|recipe| recipe.in_trash)
+                .count(),
+            sync: self.sync,
+            logged_in: self.logged_in,
+            profiles: &self.settings.profiles,
+            profile: self.settings.current_profile(),
+            settings: &self.settings,
+            theme,
+            pane_widths,
+            search_index: &self.search_index,
+        };
+
+        let focus = self.focus;
+        let lists = self.list_panes();
+
         let pane_grid = PaneGrid::new(&mut self.panes, |id, pane| {
-            pane_grid::Content::new(pane.content.view(id, 2, pane.is_nav_pane)).style(style::Pane {
-                is_nav_pane: pane.is_nav_pane,
-            })
-        });
-        pane_grid.into()
+            let is_focused = focus == Some(id);
+            let Pane { kind, controls } = pane;
+            let is_nav_pane = matches!(kind, PaneKind::Nav(_));
+            let closable = kind.closable(lists);
+
+            let title_bar = pane_grid::TitleBar::new(Text::new(kind.title()).size(16))
+                .controls(controls.view(id, !is_nav_pane, closable, theme))
+                .padding(5)
+                .style(style::TitleBar { theme, is_focused });
+            pane_grid::Content::new(kind.view(id, &context))
+                .title_bar(title_bar)
+                .style(style::Pane {
+                    theme,
+                    is_nav_pane,
+                    is_focused,
+                })
+        })
+        .on_click(Message::Clicked)
+        .on_drag(Message::Dragged)
+        .on_resize(10, Message::Resized);
+
+        let mut content = Column::new();
+        if let Some(palette) = &mut self.palette {
+            let results = palette.results(&self.recipes.lock().unwrap(), &self.settings.shortcuts);
+            content = content.push(
+                Container::new(palette.view(results, theme))
+                    .width(Length::Fill)
+                    .style(theme),
+            );
+        }
+        if let Some((discard, losses)) = &self.pending_discard {
+            let question = Row::new()
+                .spacing(10)
+                .align_items(Align::Center)
+                .push(Text::new(discard.question(*losses)).width(Length::Fill))
+                .push(
+                    Button::new(
+                        &mut self.confirm_discard,
+                        Text::new(discard.confirm_label()),
+                    )
+                    .style(theme)
+                    .on_press(Message::DiscardConfirmed),
+                )
+                .push(
+                    Button::new(&mut self.cancel_discard, Text::new("Cancel"))
+                        .style(theme)
+                        .on_press(Message::DiscardCancelled),
+                );
+            content = content.push(
+                Container::new(question)
+                    .width(Length::Fill)
+                    .padding(5)
+                    .style(theme),
+            );
+        }
+        content = content.push(pane_grid);
+
+        if let Some(status) = &self.status {
+            let status_bar = Row::new()
+                .spacing(10)
+                .align_items(Align::Center)
+                .push(Text::new(status.to_string()).width(Length::Fill))
+                .push(
+                    Button::new(&mut self.dismiss_status, Text::new("Dismiss"))
+                        .style(theme)
+                        .on_press(Message::ErrorDismissed),
+                );
+
+            content = content.push(
+                Container::new(status_bar)
+                    .width(Length::Fill)
+                    .padding(5)
+                    .style(style::StatusBar(theme)),
+            );
+        }
+
+        content.into()
     }
 
     fn title(&self) -> String {
@@ -180,20 +1056,1029 @@ impl Application for HomePage {
     }
 
     fn subscription(&self) -> Subscription<Message> {
+        let events = subscription::events_with(|event, status| match event {
+            Event::Window(window::Event::CloseRequested) => Some(Message::CloseRequested),
+            Event::Window(window::Event::Resized { width, height }) => {
+                Some(Message::WindowResized(width, height))
+            }
+            // a focused text box claims every key, but shortcuts and moving around the palette
+            // still have to work from inside one
+            Event::Keyboard(keyboard::Event::KeyPressed {
+                key_code,
+                modifiers,
+            }) if status == event::Status::Ignored
+                || modifiers.is_command_pressed()
+                || modifiers.alt
+                || matches!(
+                    key_code,
+                    keyboard::KeyCode::Escape | keyboard::KeyCode::Up | keyboard::KeyCode::Down
+                ) =>
+            {
+                Some(Message::KeyPressed(key_code, modifiers))
+            }
+            _ => None,
+        });
+        if self.logging_out {
+            return events;
+        }
+        let sync = iced::Subscription::from_recipe(RecipeSync {
+            paprika: self.paprika.clone(),
+            id: self.sync_session,
+            db: self.db.clone(),
+            settings: self.settings.sync,
+            wake: self.sync_wake.clone(),
+        })
+        .map(Message::Sync);
+        Subscription::batch(vec![events, sync])
+    }
+
+    fn background_color(&self) -> iced::Color {
+        self.theme().background
+    }
+
+    fn should_exit(&self) -> bool {
+        self.exiting
+    }
+}
+
+impl HomePage {
+    /// Makes sure `recipe`'s photo is on disk, downloading it in the background if needed
+    fn fetch_photo(&mut self, recipe: &paprika_api::api::Recipe) -> Command<Message> {
+        let (key, url) = match (
+            photo_cache::photo_key(recipe),
+            photo_cache::photo_url(recipe),
+        ) {
+            (Some(key), Some(url)) => (key, url.to_string()),
+            _ => return Command::none(),
+        };
+        if self.photos.contains_key(&key) || self.photo_downloads.contains(&key) {
+            return Command::none();
+        }
+
+        let photo_dir = photo_cache::photo_dir(&self.paths.cache_dir);
+        if let Some(photo) = photo_cache::cached(&photo_dir, &key) {
+            self.photos.insert(key, photo);
+            return Command::none();
+        }
+        // once the cache is full, recipes without a photo yet keep the placeholder
+        let full = self
+            .settings
+            .cache
+            .photo_limit()
+            .is_some_and(|limit| self.photo_bytes >= limit);
+        if full {
+            return Command::none();
+        }
+
+        self.photo_downloads.insert(key.clone());
+        let limit = self.photo_limit.clone();
+        Command::perform(
+            async move {
+                let _slot = limit.acquire().await;
+                let photo = photo_cache::download(&photo_dir, &key, &url).await;
+                (key, photo)
+            },
+            |(key, photo)| Message::PhotoFetched(key, photo),
+        )
+    }
+
+    fn fetch_categories(&self) -> Command<Message> {
+        let paprika = self.paprika.clone();
+        Command::perform(
+            async move { paprika.lock().await.fetch_categories().await },
+            Message::CategoriesFetched,
+        )
+    }
+
+    /// Takes on a new category list and remembers it for the next start
+    fn categories_changed(&mut self, categories: Vec<Category>) {
+        if let Err(e) = category::save_to_cache(&self.paths.cache_dir, &categories) {
+            self.status = Some(e);
+        }
+        if let Some(uid) = &self.selected_category {
+            if !categories.iter().any(|category| &category.uid == uid) {
+                self.selected_category = None;
+            }
+        }
+        self.categories = categories;
+    }
+
+    /// Uploads the edited categories, then takes deleted ones off any recipes that had them. Those
+    /// recipes go out through the sync engine like any other edit.
+    fn save_categories(
+        &self,
+        pane: pane_grid::Pane,
+        categories: Vec<Category>,
+        deleted: Vec<String>,
+    ) -> Command<Message> {
+        let paprika = self.paprika.clone();
+        let db = self.db.clone();
+        let affected: Vec<(paprika_api::api::Recipe, paprika_api::api::Recipe)> = self
+            .recipes
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|recipe| recipe.categories.iter().any(|uid| deleted.contains(uid)))
+            .map(|recipe| {
+                let mut updated = paprika_api::api::Recipe {
+                    categories: recipe
+                        .categories
+                        .iter()
+                        .filter(|uid| !deleted.contains(uid))
+                        .cloned()
+                        .collect(),
+                    ..recipe.clone()
+                };
+                backend::prepare_upload(&mut updated);
+                (updated, recipe.clone())
+            })
+            .collect();
+
+        Command::perform(
+            async move {
+                paprika.lock().await.update_categories(&categories).await?;
+
+                let db = db.lock().unwrap();
+                let mut updated = Vec::with_capacity(affected.len());
+                for (recipe, opened) in affected {
+                    db.save_edit(&recipe, &opened)?;
+                    updated.push(recipe);
+                }
+                Ok((categories, updated))
+            },
+            move |result| Message::CategoriesSaved(pane, result),
+        )
+    }
+
+    /// Takes on settings saved from the settings pane. A profile that syncs somewhere new starts
+    /// over, since its recipes and login belonged to the old backend.
+    fn settings_changed(&mut self, settings: Settings) -> Command<Message> {
+        let backend_changed =
+            settings.current_profile().backend != self.settings.current_profile().backend;
+        let cache_changed = settings.cache_dir != self.settings.cache_dir;
+        if settings.sync != self.settings.sync {
+            self.photo_limit = Arc::new(DownloadLimit::new(settings.sync));
+        }
+        self.settings = settings;
+        if let Err(e) = self.settings.save(&self.root_paths.settings_file()) {
+            self.status = Some(e);
+        }
+
+        if backend_changed {
+            if let Err(e) =
+                credentials::remove(&credentials::credentials_file(&self.paths.data_dir))
+            {
+                self.status = Some(e);
+            }
+            self.clear_local_data();
+        }
+        if cache_changed {
+            self.move_cache();
+        }
+        if backend_changed {
+            return self.open_profile();
+        }
+        self.prune_photos();
+        if cache_changed {
+            let recipes = self.recipes.lock().unwrap().clone();
+            return Command::batch(recipes.iter().map(|recipe| self.fetch_photo(recipe)));
+        }
+        Command::none()
+    }
+
+    /// Moves the cache to where the settings now say, taking what's been downloaded along
+    fn move_cache(&mut self) {
+        let root_paths = match Paths::resolve(
+            self.root_paths.config_dir.clone(),
+            self.settings.cache_dir.as_deref(),
+        ) {
+            Ok(root_paths) => root_paths,
+            Err(e) => {
+                self.status = Some(e);
+                return;
+            }
+        };
+        if let Err(e) = paths::move_cache(&self.root_paths.cache_dir, &root_paths.cache_dir) {
+            self.status = Some(e);
+        }
+        self.root_paths = root_paths;
+        self.paths = self
+            .root_paths
+            .for_profile(&self.settings.current_profile().id);
+        self.photos.clear();
+        self.photo_downloads.clear();
+    }
+
+    /// Deletes the oldest photos if they've outgrown the cache limit
+    fn prune_photos(&mut self) {
+        let photo_dir = photo_cache::photo_dir(&self.paths.cache_dir);
+        let limit = self.settings.cache.photo_limit().unwrap_or(u64::MAX);
+        match photo_cache::prune(&photo_dir, limit) {
+            Ok(bytes) => self.photo_bytes = bytes,
+            Err(e) => self.status = Some(e),
+        }
+        self.photos.retain(|_, photo| photo.full.is_file());
+    }
+
+    fn theme(&self) -> Theme {
+        theme::find(&self.themes, self.settings.theme.as_deref())
+            .map_or_else(Theme::light, |named| named.theme)
+    }
+
+    /// Re-sorts the recipe list and remembers the new order for next time
+    fn sort_changed(&mut self) {
+        self.settings
+            .sort
+            .sort(&mut self.recipes.lock().unwrap(), &self.edited);
+        if let Err(e) = self.settings.save(&self.root_paths.settings_file()) {
+            self.status = Some(e);
+        }
+    }
+
+    /// Re-reads which recipes still have changes waiting to be uploaded or merged
+    fn refresh_unsynced(&mut self) {
+        let db = self.db.lock().unwrap();
+        match db.unsynced() {
+            Ok(unsynced) => self.unsynced = unsynced.into_iter().collect(),
+            Err(e) => self.status = Some(e),
+        }
+        match db.conflicted() {
+            Ok(conflicts) => self.conflicts = conflicts.into_iter().collect(),
+            Err(e) => self.status = Some(e),
+        }
+    }
+
+    /// Shows the merge view for a recipe whose edits clash, next to the recipe list
+    fn open_merge(&mut self, uid: &str) {
+        let already_open = self.panes.iter().any(|(_, pane)| match &pane.kind {
+            PaneKind::Merge(merge) => merge.uid() == uid,
+            _ => false,
+        });
+        if already_open {
+            return;
+        }
+
+        let conflict = self.db.lock().unwrap().conflict(uid);
+        match conflict {
+            Ok(Some((base, mine, theirs))) => {
+                self.split_list_pane(PaneKind::Merge(RecipeMerge::new(base, mine, theirs)))
+            }
+            Ok(None) => (),
+            Err(e) => self.status = Some(e),
+        }
+    }
+
+    /// Switches over to the profile picked in the settings: its backend, saved login, recipes
+    /// and cache. Everything shown for the previous profile is closed.
+    /// Switches to the profile `id`, asking first if that would close unsaved work
+    fn select_profile(&mut self, id: String) -> Command<Message> {
+        if self.settings.current_profile().id == id {
+            return Command::none();
+        }
+        match self.unsaved_panes() {
+            0 => self.switch_profile(id),
+            n => {
+                let losses = Losses {
+                    unsaved: n,
+                    ..Losses::default()
+                };
+                self.pending_discard = Some((PendingDiscard::SwitchProfile(id), losses));
+                Command::none()
+            }
+        }
+    }
+
+    fn switch_profile(&mut self, id: String) -> Command<Message> {
+        self.settings.profile = Some(id);
+        if let Err(e) = self.settings.save(&self.root_paths.settings_file()) {
+            self.status = Some(e);
+        }
+        let command = self.open_profile();
+        self.offer_unlock();
+        command
+    }
+
+    fn open_profile(&mut self) -> Command<Message> {
+        // a question about the last profile's changes doesn't apply to this one
+        self.pending_discard = None;
+        let profile = self.settings.current_profile();
+        self.paths = self.root_paths.for_profile(&profile.id);
+        let is_default = profile.id == profile::DEFAULT_ID;
+        if is_default {
+            if let Err(e) = profile::migrate_unprofiled(&self.root_paths, &self.paths) {
+                self.status = Some(e);
+            }
+        }
+        let mut paprika = paprika::Paprika::with_backend(profile.backend.build());
+        paprika.env_login = is_default;
+        self.paprika = Arc::new(tokio::sync::Mutex::new(paprika));
+
+        self.categories = category::load_cached(&self.paths.cache_dir).unwrap_or_else(|e| {
+            self.status = Some(e);
+            Vec::new()
+        });
+
+        // everything we've seen before is shown straight away, connected or not
+        let db = RecipeDb::open(&recipe_db::db_file(&self.paths.data_dir))
+            .or_else(|e| {
+                self.status = Some(e);
+                RecipeDb::open_in_memory()
+            })
+            .expect("Couldn't create an in-memory recipe database");
+        // the one-file-per-recipe cache predates profiles, so it can only be the default's
+        if is_default {
+            match db.import_json_cache(&self.root_paths.cache_dir) {
+                Ok(_) => (),
+                Err(e) => self.status = Some(e),
+            }
+        }
+        let stored = db
+            .recipes()
+            .and_then(|stored| Ok((stored, db.updated_times()?, db.last_synced()?)));
+        let (stored, edited, last_synced) = stored.unwrap_or_else(|e| {
+            self.status = Some(e);
+            Default::default()
+        });
+        self.db = Arc::new(Mutex::new(db));
+        {
+            let mut recipes = self.recipes.lock().unwrap();
+            *recipes = stored;
+            self.settings.sort.sort(&mut recipes, &edited);
+        }
+        self.edited = edited;
+        self.refresh_unsynced();
+        self.photos.clear();
+        self.photo_downloads.clear();
+        self.prune_photos();
+        self.selected_category = None;
+        self.logged_in = false;
+
+        self.close_panes(|kind| {
+            matches!(
+                kind,
+                PaneKind::Detail(_)
+                    | PaneKind::Editor(_)
+                    | PaneKind::Merge(_)
+                    | PaneKind::Categories(_)
+                    | PaneKind::Login(_)
+                    | PaneKind::Settings(_)
+            )
+        });
+        self.restart_sync();
+        self.sync.last_synced = last_synced;
+
+        let recipes = self.recipes.lock().unwrap().clone();
+        let mut commands = vec![self.fetch_categories()];
+        for recipe in &recipes {
+            commands.push(self.fetch_photo(recipe));
+        }
+        Command::batch(commands)
+    }
+
+    /// Asks for the passphrase if the profile has a saved login
+    fn offer_unlock(&mut self) {
+        if credentials::credentials_file(&self.paths.data_dir).is_file() {
+            self.split_list_pane(PaneKind::Login(LoginForm::unlock()));
+        }
+    }
+
+    /// Arranges the panes the way they were left last time, or the default way the first time
+    fn restore_layout(&mut self) {
+        let saved =
+            Layout::load(&layout::layout_file(&self.root_paths.config_dir)).unwrap_or_else(|e| {
+                self.status = Some(e);
+                None
+            });
+        let layout = saved
+            .filter(|layout| layout.contains(&|pane| *pane == SavedPane::Nav))
+            .unwrap_or_else(|| Layout::new(self.settings.window.nav_ratio));
+
+        let recipes = self.recipes.clone();
+        let configuration = layout.into_configuration(&mut |saved| {
+            let kind = match saved {
+                SavedPane::Nav => PaneKind::Nav(NavPane::new()),
+                SavedPane::List => PaneKind::List(Content::new(recipes.clone())),
+                SavedPane::Detail { uid } => {
+                    let recipe = recipes
+                        .lock()
+                        .unwrap()
+                        .iter()
+                        .find(|recipe| recipe.uid == uid)
+                        .cloned()?;
+                    PaneKind::Detail(RecipeView::new(recipe))
+                }
+                SavedPane::ShoppingList => {
+                    PaneKind::ShoppingList(ShoppingList::new(recipes.clone()))
+                }
+                SavedPane::Trash => PaneKind::Trash(TrashView::new(recipes.clone())),
+            };
+            Some(Pane::new(kind))
+        });
+        if let Some(configuration) = configuration {
+            self.panes = pane_grid::State::with_configuration(configuration);
+            self.focus = None;
+            self.last_detail = None;
+        }
+    }
+
+    /// Remembers the layout for next time
+    fn save_layout(&self) -> error::Result<()> {
+        let panes = &self.panes;
+        let layout = Layout::capture(panes.layout(), &|pane| {
+            panes.get(pane).and_then(|pane| pane.kind.saved())
+        });
+        match layout {
+            Some(layout) => layout.save(&layout::layout_file(&self.root_paths.config_dir)),
+            None => Ok(()),
+        }
+    }
+
+    fn focus_pane(&mut self, pane: pane_grid::Pane) {
+        self.focus = Some(pane);
+        if let Some(PaneKind::Detail(_)) = self.panes.get(&pane).map(|pane| &pane.kind) {
+            self.last_detail = Some(pane);
+        }
+    }
+
+    fn list_panes(&self) -> usize {
+        self.panes
+            .iter()
+            .filter(|(_, pane)| matches!(pane.kind, PaneKind::List(_)))
+            .count()
+    }
+
+    /// The pane shortcuts act on: the focused one, or else the recipe list
+    fn active_pane(&self) -> Option<pane_grid::Pane> {
+        self.focus
+            .filter(|pane| self.panes.get(pane).is_some())
+            .or_else(|| {
+                self.panes
+                    .iter()
+                    .find(|(_, pane)| matches!(pane.kind, PaneKind::List(_)))
+                    .map(|(id, _)| *id)
+            })
+    }
+
+    /// The recipe list shortcuts act on: the focused one, or else the first
+    fn active_list(&self) -> Option<pane_grid::Pane> {
+        let is_list = |pane: &pane_grid::Pane| {
+            matches!(
+                self.panes.get(pane).map(|pane| &pane.kind),
+                Some(PaneKind::List(_))
+            )
+        };
+        self.focus.filter(is_list).or_else(|| {
+            self.panes
+                .iter()
+                .map(|(id, _)| *id)
+                .find(|pane| is_list(pane))
+        })
+    }
+
+    /// The recipe being looked at: in the focused pane, else the recipe pane focused last
+    fn current_recipe(&self) -> Option<String> {
+        let recipe_in = |pane: &pane_grid::Pane| match self.panes.get(pane).map(|pane| &pane.kind) {
+            Some(PaneKind::Detail(detail)) => Some(detail.recipe.uid.clone()),
+            _ => None,
+        };
+        self.focus
+            .and_then(|pane| recipe_in(&pane))
+            .or_else(|| self.last_detail.and_then(|pane| recipe_in(&pane)))
+            .or_else(|| self.panes.iter().find_map(|(pane, _)| recipe_in(pane)))
+    }
+
+    /// Does what a shortcut or the command palette asked for
+    fn run_action(&mut self, action: Action, clipboard: &mut Clipboard) -> Command<Message> {
+        let pane = self.active_pane();
+        let message = match action {
+            Action::CommandPalette => {
+                self.palette = match self.palette {
+                    Some(_) => None,
+                    None => {
+                        self.focus_search(None);
+                        Some(CommandPalette::new())
+                    }
+                };
+                return Command::none();
+            }
+            Action::NewRecipe => Message::NewRecipeClicked,
+            Action::FocusSearch => {
+                let list = self.active_list();
+                self.focus_search(list);
+                if let Some(list) = list {
+                    self.focus_pane(list);
+                }
+                return Command::none();
+            }
+            Action::SyncNow => Message::SyncNowClicked,
+            Action::NextRecipe => {
+                self.step_recipe(1);
+                return Command::none();
+            }
+            Action::PreviousRecipe => {
+                self.step_recipe(-1);
+                return Command::none();
+            }
+            Action::EditRecipe => match self.current_recipe() {
+                Some(uid) => Message::EditRecipeClicked(uid),
+                None => return Command::none(),
+            },
+            Action::SplitRight | Action::SplitDown => {
+                let axis = if action == Action::SplitRight {
+                    Axis::Vertical
+                } else {
+                    Axis::Horizontal
+                };
+                match pane.filter(|pane| {
+                    !matches!(
+                        self.panes.get(pane).map(|pane| &pane.kind),
+                        Some(PaneKind::Nav(_))
+                    )
+                }) {
+                    Some(pane) => Message::Split(axis, pane),
+                    None => return Command::none(),
+                }
+            }
+            Action::ClosePane => {
+                let lists = self.list_panes();
+                match pane.filter(|pane| {
+                    self.panes
+                        .get(pane)
+                        .is_some_and(|pane| pane.kind.closable(lists))
+                }) {
+                    Some(pane) => Message::Close(pane),
+                    None => return Command::none(),
+                }
+            }
+            Action::ManageCategories => Message::ManageCategoriesClicked,
+            Action::ShowShoppingList => Message::ShoppingListClicked,
+            Action::ShowTrash => Message::TrashClicked,
+        };
+        self.update(message, clipboard)
+    }
+
+    /// Puts the cursor in the search box of the recipe list `pane`, taking it out of every other
+    fn focus_search(&mut self, pane: Option<pane_grid::Pane>) {
+        for (id, list) in self.panes.iter_mut() {
+            if let PaneKind::List(content) = &mut list.kind {
+                content.filter.focus_search(Some(*id) == pane);
+            }
+        }
+    }
+
+    /// Shows the recipe `by` places further down the recipe list than the current one, going by
+    /// what the list shows
+    fn step_recipe(&mut self, by: isize) {
+        let list = self.active_list().and_then(|pane| self.panes.get(&pane));
+        let list = match list.map(|pane| &pane.kind) {
+            Some(PaneKind::List(list)) => list,
+            _ => return,
+        };
+
+        let listed: Vec<paprika_api::api::Recipe> = self
+            .recipes
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|recipe| !recipe.in_trash && list.filter.matches(recipe, &self.search_index))
+            .cloned()
+            .collect();
+        if listed.is_empty() {
+            return;
+        }
+        let current = self
+            .current_recipe()
+            .and_then(|uid| listed.iter().position(|recipe| recipe.uid == uid));
+        let index = match current {
+            Some(index) => (index as isize + by).clamp(0, listed.len() as isize - 1) as usize,
+            None if by < 0 => listed.len() - 1,
+            None => 0,
+        };
+        if Some(index) != current {
+            self.show_recipe(listed[index].clone());
+        }
+    }
+
+    /// Closes `pane`, handing its focus to the pane that takes its place
+    fn close_pane(&mut self, pane: pane_grid::Pane) {
+        if let Some((_, sibling)) = self.panes.close(&pane) {
+            if self.focus == Some(pane) {
+                self.focus = Some(sibling);
+            }
+        }
+    }
+
+    fn close_panes(&mut self, close: impl Fn(&PaneKind) -> bool) {
+        let closing: Vec<pane_grid::Pane> = self
+            .panes
+            .iter()
+            .filter(|(_, pane)| close(&pane.kind))
+            .map(|(id, _)| *id)
+            .collect();
+        for pane in closing {
+            self.close_pane(pane);
+        }
+    }
+
+    /// How many editor and merge panes would lose changes if they were closed
+    fn unsaved_panes(&self) -> usize {
+        self.panes
+            .iter()
+            .filter(|(_, pane)| match &pane.kind {
+                PaneKind::Editor(editor) => editor.has_changes(),
+                PaneKind::Merge(merge) => merge.has_changes(),
+                _ => false,
+            })
+            .count()
+    }
+
+    /// What clearing the local data would lose: edits, deletions and unmerged conflicts waiting
+    /// to upload, and open editors that haven't been saved
+    fn clearing_losses(&self) -> Result<Losses, Error> {
+        let unuploaded = self.db.lock().unwrap().unsynced()?.len();
+        Ok(Losses {
+            unuploaded,
+            unsaved: self.unsaved_panes(),
+        })
+    }
+
+    fn log_out(&mut self) -> Command<Message> {
+        self.logging_out = true;
         let paprika = self.paprika.clone();
-        let test = iced::Subscription::from_recipe(RecipeFetcher {
-            paprika: paprika,
-            id: 0,
+        let file = credentials::credentials_file(&self.paths.data_dir);
+        Command::perform(
+            async move {
+                paprika.lock().await.log_out();
+                credentials::remove(&file)
+            },
+            Message::LoggedOut,
+        )
+    }
+
+    /// Replaces the sync engine, dropping anything the old one had in flight
+    fn restart_sync(&mut self) {
+        self.sync_session += 1;
+        self.sync = SyncProgress {
+            last_synced: self.sync.last_synced,
+            ..Default::default()
+        };
+    }
+
+    /// Logs in with a password or the saved token, reporting back to the login form in `pane`
+    fn log_in(&self, pane: pane_grid::Pane, request: LoginRequest) -> Command<Message> {
+        let paprika = self.paprika.clone();
+        let file = credentials::credentials_file(&self.paths.data_dir);
+        Command::perform(
+            async move {
+                match request {
+                    LoginRequest::Password(account, passphrase) => {
+                        let mut paprika = paprika.lock().await;
+                        paprika.log_in(&account).await?;
+                        match passphrase {
+                            Some(passphrase) => {
+                                credentials::save(&file, &paprika.token, &passphrase)
+                            }
+                            // a login that isn't remembered replaces any that was
+                            None => credentials::remove(&file),
+                        }
+                    }
+                    LoginRequest::Unlock(passphrase) => {
+                        let token = credentials::load(&file, &passphrase)?;
+                        let mut paprika = paprika.lock().await;
+                        paprika.log_out();
+                        paprika.token = token;
+                        Ok(())
+                    }
+                }
+            },
+            move |result| Message::LoggedIn(pane, result),
+        )
+    }
+
+    /// Forgets every recipe, photo and category downloaded for the account that logged out,
+    /// closing the panes that showed them
+    fn clear_local_data(&mut self) {
+        let cleared = self
+            .db
+            .lock()
+            .unwrap()
+            .clear()
+            .and_then(|_| photo_cache::clear(&photo_cache::photo_dir(&self.paths.cache_dir)))
+            .and_then(|_| category::clear_cache(&self.paths.cache_dir));
+        if let Err(e) = cleared {
+            self.status = Some(e);
+        }
+
+        self.recipes.lock().unwrap().clear();
+        self.edited.clear();
+        self.unsynced.clear();
+        self.conflicts.clear();
+        self.photos.clear();
+        self.photo_downloads.clear();
+        self.categories.clear();
+        self.selected_category = None;
+        self.sync = SyncProgress::default();
+
+        self.close_panes(|kind| {
+            matches!(
+                kind,
+                PaneKind::Detail(_)
+                    | PaneKind::Editor(_)
+                    | PaneKind::Merge(_)
+                    | PaneKind::Categories(_)
+            )
         });
-        test.map(|recipe| Message::RecipeFetched(recipe))
+    }
+
+    /// Forgets a deleted recipe, closing anything showing it
+    fn recipe_removed(&mut self, uid: &str) {
+        self.recipes
+            .lock()
+            .unwrap()
+            .retain(|recipe| recipe.uid != uid);
+        self.edited.remove(uid);
+        self.unsynced.remove(uid);
+
+        let showing: Vec<pane_grid::Pane> = self
+            .panes
+            .iter()
+            .filter(|(_, pane)| match &pane.kind {
+                PaneKind::Detail(detail) => detail.recipe.uid == uid,
+                _ => false,
+            })
+            .map(|(id, _)| *id)
+            .collect();
+        for pane in showing {
+            self.close_pane(pane);
+        }
+    }
+
+    /// Makes a small change to a recipe, like moving it to the trash, and uploads it like any
+    /// other edit
+    fn change_recipe(
+        &self,
+        pane: pane_grid::Pane,
+        uid: &str,
+        change: impl FnOnce(&mut paprika_api::api::Recipe),
+    ) -> Command<Message> {
+        match self.find_recipe(uid) {
+            Some(mut recipe) => {
+                let opened = recipe.clone();
+                change(&mut recipe);
+                self.save_recipe(pane, recipe, EditedFrom::Opened(opened))
+            }
+            None => Command::none(),
+        }
+    }
+
+    /// Removes trashed recipes from this device, leaving the sync engine to tell the backend.
+    /// The backend only keeps them in its trash; see `TrashView`.
+    fn delete_for_good(&mut self, uids: Vec<String>) {
+        if uids.is_empty() {
+            return;
+        }
+
+        let deleted = {
+            let db = self.db.lock().unwrap();
+            uids.iter().try_for_each(|uid| db.delete_locally(uid))
+        };
+        if let Err(e) = deleted {
+            self.status = Some(e);
+        }
+        for uid in &uids {
+            self.recipe_removed(uid);
+        }
+        self.refresh_unsynced();
+        self.sync_wake.notify_one();
+    }
+
+    fn find_recipe(&self, uid: &str) -> Option<paprika_api::api::Recipe> {
+        self.recipes
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|recipe| recipe.uid == uid)
+            .cloned()
+    }
+
+    /// Shows `recipe` in the recipe pane focused last, opening one next to the recipe list if
+    /// there isn't one
+    fn show_recipe(&mut self, recipe: paprika_api::api::Recipe) {
+        let is_detail = |pane: &Pane| matches!(pane.kind, PaneKind::Detail(_));
+        let target = self
+            .last_detail
+            .filter(|id| self.panes.get(id).is_some_and(is_detail))
+            .or_else(|| {
+                self.panes
+                    .iter()
+                    .find(|(_, pane)| is_detail(pane))
+                    .map(|(id, _)| *id)
+            });
+
+        match target.and_then(|id| self.panes.get_mut(&id)) {
+            Some(Pane {
+                kind: PaneKind::Detail(detail),
+                ..
+            }) => *detail = RecipeView::new(recipe),
+            _ => self.split_list_pane(PaneKind::Detail(RecipeView::new(recipe))),
+        }
+    }
+
+    /// Shows `editor` next to the recipe list
+    fn open_editor(&mut self, editor: RecipeEditor) {
+        self.split_list_pane(PaneKind::Editor(editor));
+    }
+
+    fn split_list_pane(&mut self, kind: PaneKind) {
+        let list_pane = self
+            .panes
+            .iter()
+            .find(|(_, pane)| matches!(pane.kind, PaneKind::List(_)))
+            .map(|(id, _)| *id);
+
+        let target = match list_pane {
+            Some(pane) => pane,
+            None => match self.panes.iter().next() {
+                Some((id, _)) => *id,
+                None => return,
+            },
+        };
+
+        if let Some((pane, _)) = self.panes.split(Axis::Vertical, &target, Pane::new(kind)) {
+            self.focus_pane(pane);
+        }
+    }
+
+    /// Stores `recipe` locally, then uploads it, reporting back to the editor or merge view in
+    /// `pane`. A merged recipe comes with `theirs`, the server copy it was merged with.
+    ///
+    /// A failed upload still counts as saved; the recipe stays in the outbox for the sync engine
+    /// to retry.
+    fn save_recipe(
+        &self,
+        pane: pane_grid::Pane,
+        recipe: paprika_api::api::Recipe,
+        from: EditedFrom,
+    ) -> Command<Message> {
+        let paprika = self.paprika.clone();
+        let db = self.db.clone();
+        Command::perform(
+            async move {
+                let mut recipe = recipe;
+                backend::prepare_upload(&mut recipe);
+                match &from {
+                    EditedFrom::Opened(opened) => db.lock().unwrap().save_edit(&recipe, opened)?,
+                    EditedFrom::Merge(theirs) => db.lock().unwrap().resolve(&recipe, theirs)?,
+                }
+
+                // the server's list has to be current to tell whether anyone else changed it
+                let mut paprika = paprika.lock().await;
+                let uploaded = match paprika.fetch_recipe_list().await {
+                    Ok(_) => recipe_sync::upload(&mut paprika, &db, recipe.clone()).await,
+                    Err(e) => Err(e),
+                };
+                match uploaded {
+                    Ok(uploaded) => Ok((uploaded, None)),
+                    Err(e) => Ok((recipe, Some(e))),
+                }
+            },
+            move |result| Message::RecipeSaved(pane, result),
+        )
     }
 }
 
 impl Pane {
-    fn new(is_nav_pane: bool, recipes: Arc<Mutex<Vec<paprika_api::api::Recipe>>>) -> Self {
+    fn new(kind: PaneKind) -> Self {
+        Self {
+            kind,
+            controls: Controls::new(),
+        }
+    }
+}
+
+impl Controls {
+    fn new() -> Self {
         Self {
-            content: Content::new(recipes),
-            is_nav_pane: is_nav_pane,
+            split_right: button::State::new(),
+            split_down: button::State::new(),
+            close: button::State::new(),
+        }
+    }
+
+    fn view(
+        &mut self,
+        pane: pane_grid::Pane,
+        splittable: bool,
+        closable: bool,
+        theme: Theme,
+    ) -> Element<'_, Message> {
+        let mut row = Row::new().spacing(5);
+        if splittable {
+            row = row
+                .push(
+                    Button::new(&mut self.split_right, Text::new("Split right").size(14))
+                        .style(theme)
+                        .on_press(Message::Split(Axis::Vertical, pane)),
+                )
+                .push(
+                    Button::new(&mut self.split_down, Text::new("Split down").size(14))
+                        .style(theme)
+                        .on_press(Message::Split(Axis::Horizontal, pane)),
+                );
+        }
+        if closable {
+            row = row.push(
+                Button::new(&mut self.close, Text::new("Close").size(14))
+                    .style(theme)
+                    .on_press(Message::Close(pane)),
+            );
+        }
+        row.into()
+    }
+}
+
+impl PaneKind {
+    /// The nav pane and the last of the `lists` recipe lists are always there
+    fn closable(&self, lists: usize) -> bool {
+        match self {
+            PaneKind::Nav(_) => false,
+            PaneKind::List(_) => lists > 1,
+            _ => true,
+        }
+    }
+
+    fn title(&self) -> String {
+        match self {
+            PaneKind::Nav(_) => "Ancho".into(),
+            PaneKind::List(_) => "Recipes".into(),
+            PaneKind::Detail(detail) => detail.recipe.name.clone(),
+            PaneKind::Editor(_) => "Edit recipe".into(),
+            PaneKind::Categories(_) => "Categories".into(),
+            PaneKind::Merge(_) => "Merge changes".into(),
+            PaneKind::Login(_) => "Log in".into(),
+            PaneKind::Profile(_) => "New profile".into(),
+            PaneKind::Settings(_) => "Settings".into(),
+            PaneKind::Trash(_) => "Trash".into(),
+            PaneKind::ShoppingList(_) => "Shopping list".into(),
+        }
+    }
+
+    /// What to bring back next time, for panes that don't hold unsaved work
+    fn saved(&self) -> Option<SavedPane> {
+        match self {
+            PaneKind::Nav(_) => Some(SavedPane::Nav),
+            PaneKind::List(_) => Some(SavedPane::List),
+            PaneKind::Detail(detail) => Some(SavedPane::Detail {
+                uid: detail.recipe.uid.clone(),
+            }),
+            PaneKind::ShoppingList(_) => Some(SavedPane::ShoppingList),
+            PaneKind::Trash(_) => Some(SavedPane::Trash),
+            _ => None,
+        }
+    }
+
+    fn view(&mut self, pane: pane_grid::Pane, context: &PaneContext<'_>) -> Element<'_, Message> {
+        match self {
+            PaneKind::Nav(nav_pane) => nav_pane.view(
+                context.categories,
+                context.selected_category,
+                &context.sync,
+                context.unsynced.len(),
+                context.trashed,
+                context.logged_in,
+                context.profiles,
+                &context.profile,
+                context.theme,
+            ),
+            PaneKind::List(content) => content.view(pane, context),
+            PaneKind::Detail(detail) => {
+                let photo = photo_cache::photo_key(&detail.recipe)
+                    .and_then(|key| context.photos.get(&key))
+                    .cloned();
+                let uid = &detail.recipe.uid;
+                let sync_state = if context.conflicts.contains(uid) {
+                    SyncState::Conflicted
+                } else if context.unsynced.contains(uid) {
+                    SyncState::Modified
+                } else {
+                    SyncState::Synced
+                };
+                detail.view(
+                    pane,
+                    context.categories,
+                    photo.as_ref(),
+                    sync_state,
+                    context.theme,
+                )
+            }
+            PaneKind::Editor(editor) => editor.view(pane, context.theme),
+            PaneKind::Categories(manager) => manager.view(pane, context.theme),
+            PaneKind::Merge(merge) => merge.view(pane, context.theme),
+            PaneKind::Login(form) => form.view(pane, context.theme),
+            PaneKind::Profile(form) => form.view(pane, context.theme),
+            PaneKind::Settings(form) => form.view(pane, context.settings, context.theme),
+            PaneKind::Trash(trash) => trash.view(pane, context.theme),
+            PaneKind::ShoppingList(list) => list.view(context.theme),
         }
     }
 }
@@ -202,68 +2087,128 @@ impl Content {
     fn new(recipes: Arc<Mutex<Vec<paprika_api::api::Recipe>>>) -> Self {
         Content {
             scroll: scrollable::State::new(),
-            nav_pane: NavPane::new(),
             recipes: recipes.clone(),
             recipe_buttons: Vec::new(),
+            recipe_cards: Vec::new(),
+            filter: RecipeFilter::new(),
+            sort_picker: pick_list::State::default(),
+            sort_direction: button::State::new(),
+            list_mode: button::State::new(),
         }
     }
-    fn view(
-        &mut self,
-        #[allow(unused)] pane: pane_grid::Pane,
-        #[allow(unused)] total_panes: usize,
-        is_nav_bar: bool,
-    ) -> Element<Message> {
-        let Content { scroll, .. } = self;
-
-        match is_nav_bar {
-            true => self.nav_pane.view(),
-            false => {
-                let controls = Column::new().spacing(5).max_width(150);
-
-                let mut content = Scrollable::new(scroll)
-                    .width(Length::Fill)
-                    .spacing(10)
-                    .align_items(Align::Center)
-                    .push(controls);
-
-                let _recipes_arc = self.recipes.clone();
-                let _recipes = _recipes_arc.lock().unwrap();
-
-                self.recipe_buttons.clear();
-                for recipe in _recipes.iter() {
-                    if !recipe.in_trash {
-                        let recipe_button;
-                        match recipe.image_url.clone() {
-                            Some(url) => {
-                                recipe_button = recipe_button::RecipeButton::new(
-                                    recipe.name.clone(),
-                                    recipe.uid.clone(),
-                                    url.clone(),
-                                )
-                            }
-                            None => {
-                                recipe_button = recipe_button::RecipeButton::new(
-                                    recipe.name.clone(),
-                                    recipe.uid.clone(),
-                                    "".into(),
-                                )
-                            }
-                        }
-                        // store the button in Content's owned Vec to allow it to live long enough
-                        self.recipe_buttons.push(recipe_button);
-                    }
+    fn view(&mut self, pane: pane_grid::Pane, context: &PaneContext<'_>) -> Element<'_, Message> {
+        let sort = context.sort;
+        let theme = context.theme;
+        let Content {
+            scroll,
+            recipes,
+            recipe_buttons,
+            recipe_cards,
+            filter,
+            sort_picker,
+            sort_direction,
+            list_mode,
+        } = self;
+
+        let _recipes_arc = recipes.clone();
+        let _recipes = _recipes_arc.lock().unwrap();
+
+        let mut content = Scrollable::new(scroll)
+            .style(theme)
+            .width(Length::Fill)
+            .spacing(10)
+            .align_items(Align::Center);
+
+        let shown = _recipes
+            .iter()
+            .filter(|recipe| !recipe.in_trash && filter.matches(recipe, context.search_index));
+        let photo = |recipe: &paprika_api::api::Recipe| {
+            photo_cache::photo_key(recipe)
+                .and_then(|key| context.photos.get(&key))
+                .cloned()
+        };
+        match context.settings.list_mode {
+            ListMode::List => {
+                recipe_buttons.clear();
+                for recipe in shown {
+                    let recipe_button = recipe_button::RecipeButton::new(
+                        recipe.name.clone(),
+                        recipe.uid.clone(),
+                        photo(recipe),
+                    );
+                    // store the button in Content's owned Vec to allow it to live long enough
+                    recipe_buttons.push(recipe_button);
                 }
-                for recipe_button in &mut self.recipe_buttons {
-                    content = content.push(recipe_button.view());
+                for recipe_button in recipe_buttons.iter_mut() {
+                    content = content.push(recipe_button.view(theme));
                 }
-
-                Container::new(content)
-                    .width(Length::Fill)
-                    .height(Length::Fill)
-                    .padding(5)
-                    .center_y()
-                    .into()
+            }
+            ListMode::Grid => {
+                *recipe_cards = shown
+                    .map(|recipe| RecipeCard::new(recipe, photo(recipe)))
+                    .collect();
+                let width = context
+                    .pane_widths
+                    .get(&pane)
+                    .map_or(0.0, |width| width - GRID_MARGIN);
+                let columns = recipe_card::columns(width);
+                let new_row = || Row::new().spacing(recipe_card::CARD_SPACING);
+                let mut row = new_row();
+                for (i, card) in recipe_cards.iter_mut().enumerate() {
+                    if i > 0 && i % columns == 0 {
+                        content = content.push(row);
+                        row = new_row();
+                    }
+                    row = row.push(card.view(theme));
+                }
+                content = content.push(row);
             }
         }
+
+        let direction = if sort.descending {
+            "Descending"
+        } else {
+            "Ascending"
+        };
+        let mode_label = match context.settings.list_mode {
+            ListMode::List => "Show photos",
+            ListMode::Grid => "Show list",
+        };
+        let sort_controls = Row::new()
+            .spacing(10)
+            .align_items(Align::Center)
+            .push(Text::new("Sort by"))
+            .push(
+                PickList::new(
+                    sort_picker,
+                    &SortKey::ALL[..],
+                    Some(sort.key),
+                    Message::SortKeyChanged,
+                )
+                .style(theme),
+            )
+            .push(
+                Button::new(sort_direction, Text::new(direction))
+                    .style(theme)
+                    .on_press(Message::SortDirectionToggled),
+            )
+            .push(
+                Button::new(list_mode, Text::new(mode_label))
+                    .style(theme)
+                    .on_press(Message::ListModeToggled),
+            );
+
+        let content = Column::new()
+            .spacing(10)
+            .push(filter.view(pane, context.categories, theme))
+            .push(sort_controls)
+            .push(content);
+
+        Container::new(content)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .padding(5)
+            .center_y()
+            .into()
     }
 }
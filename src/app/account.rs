@@ -1,8 +1,7 @@
-use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha256};
-use hex;
+use zeroize::Zeroize;
 
-#[derive(Debug, Serialize, Deserialize, Hash)]
+/// What the login dialog collects. It's only kept until it has been traded for a token, and the
+/// password is wiped from memory when it's dropped.
 pub struct Account {
     pub username: String,
     pub password: String,
@@ -10,20 +9,12 @@ pub struct Account {
 
 impl Account {
     pub fn new(username: String, password: String) -> Self {
-        {
-            Self {
-                username: username,
-                password: password,
-            }
-        }
+        Self { username, password }
     }
-    
-    pub fn get_hash(&self) -> String {
-        let mut hasher = Sha256::new();
-        
-        let serialized = serde_json::to_string(&self).unwrap();
-        hasher.update(serialized);
+}
 
-        hex::encode(hasher.finalize())
+impl Drop for Account {
+    fn drop(&mut self) {
+        self.password.zeroize();
     }
 }
@@ -0,0 +1,118 @@
+mod local_directory;
+#[cfg(test)]
+pub mod mock_server;
+mod paprika_cloud;
+
+pub use local_directory::LocalDirectory;
+pub use paprika_cloud::PaprikaCloud;
+
+use crate::app::account::Account;
+use crate::app::category::Category;
+use crate::app::error::Error;
+
+use async_trait::async_trait;
+use paprika_api::api::{Recipe, RecipeEntry};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{env, fmt, path};
+use uuid::Uuid;
+
+pub type BackendResult<T> = Result<T, Error>;
+
+/// A recipe store that Ancho can sync with.
+///
+/// `login` hands out a token that is passed back into every other call; backends that don't
+/// need one are free to return a placeholder.
+#[async_trait]
+pub trait RecipeBackend: Send + Sync {
+    /// Whether `login` needs a username and password to succeed
+    fn requires_credentials(&self) -> bool {
+        true
+    }
+
+    async fn login(&self, account: &Account) -> BackendResult<String>;
+
+    /// Lists the uid and hash of every recipe in the store
+    async fn recipe_entries(&self, token: &str) -> BackendResult<Vec<RecipeEntry>>;
+
+    async fn recipe(&self, token: &str, uid: &str) -> BackendResult<Recipe>;
+
+    /// Creates a recipe (when `recipe.uid` is empty) or updates an existing one, filling in the
+    /// uid and hash the store assigned
+    async fn upload_recipe(&self, token: &str, recipe: &mut Recipe) -> BackendResult<()>;
+
+    /// Moves a recipe to the store's trash: it stays listed, with `in_trash` set, so every
+    /// device syncing from the store sees it trashed rather than missing
+    async fn delete_recipe(&self, token: &str, uid: &str) -> BackendResult<()>;
+
+    async fn categories(&self, token: &str) -> BackendResult<Vec<Category>>;
+
+    /// Replaces every category in the store with `categories`
+    async fn upload_categories(&self, token: &str, categories: &[Category]) -> BackendResult<()>;
+}
+
+/// Which backend a profile syncs with, as stored in the settings file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BackendConfig {
+    PaprikaCloud,
+    /// A Paprika-compatible server at `url`
+    Server {
+        url: String,
+    },
+    LocalDirectory {
+        path: path::PathBuf,
+    },
+}
+
+impl BackendConfig {
+    /// A local directory when `ANCHO_RECIPE_DIR` is set, otherwise the Paprika cloud (or the
+    /// Paprika-compatible server at `PAPRIKA_API_URL`). Only used to set up the first profile.
+    pub fn from_env() -> Self {
+        if let Ok(dir) = env::var("ANCHO_RECIPE_DIR") {
+            return BackendConfig::LocalDirectory { path: dir.into() };
+        }
+
+        match env::var("PAPRIKA_API_URL") {
+            Ok(url) => BackendConfig::Server { url },
+            Err(_) => BackendConfig::PaprikaCloud,
+        }
+    }
+
+    pub fn build(&self) -> Box<dyn RecipeBackend> {
+        match self {
+            BackendConfig::PaprikaCloud => Box::new(PaprikaCloud::new()),
+            BackendConfig::Server { url } => Box::new(PaprikaCloud::with_base_url(url)),
+            BackendConfig::LocalDirectory { path } => Box::new(LocalDirectory::new(path)),
+        }
+    }
+}
+
+impl fmt::Display for BackendConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BackendConfig::PaprikaCloud => write!(f, "Paprika cloud"),
+            BackendConfig::Server { url } => write!(f, "Server at {}", url),
+            BackendConfig::LocalDirectory { path } => write!(f, "Folder {}", path.display()),
+        }
+    }
+}
+
+/// Hashes a recipe the same way Paprika does: SHA-256 over its JSON, with the old hash cleared
+pub fn recipe_hash(recipe: &Recipe) -> String {
+    let mut recipe = recipe.clone();
+    recipe.hash.clear();
+
+    let mut hasher = Sha256::new();
+    hasher.update(serde_json::to_string(&recipe).unwrap_or_default());
+
+    hex::encode(hasher.finalize())
+}
+
+/// Gives new recipes a uid and stamps the hash a store should report for `recipe`
+pub fn prepare_upload(recipe: &mut Recipe) {
+    if recipe.uid.is_empty() {
+        recipe.uid = Uuid::new_v4().to_string().to_uppercase();
+    }
+    recipe.hash = recipe_hash(recipe);
+}
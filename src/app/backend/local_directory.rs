@@ -0,0 +1,153 @@
+use super::{prepare_upload, recipe_hash, BackendResult, RecipeBackend};
+use crate::app::account::Account;
+use crate::app::category::Category;
+
+use async_trait::async_trait;
+use paprika_api::api::{Recipe, RecipeEntry};
+use std::{fs, path};
+
+const LOCAL_TOKEN: &str = "local";
+const CATEGORIES_FILE: &str = "categories.json";
+
+/// A directory of `<uid>.json` recipe files, e.g. a self-hosted store synced by other tools.
+/// Categories live next to them in `categories.json`.
+pub struct LocalDirectory {
+    root: path::PathBuf,
+}
+
+impl LocalDirectory {
+    pub fn new(root: impl Into<path::PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn recipe_path(&self, uid: &str) -> path::PathBuf {
+        self.root.join(format!("{}.json", uid))
+    }
+
+    fn read_recipe(path: &path::Path) -> BackendResult<Recipe> {
+        let recipe_string = fs::read_to_string(path)?;
+        let mut recipe: Recipe = serde_json::from_str(&recipe_string)?;
+
+        // files written by hand won't carry a hash
+        if recipe.hash.is_empty() {
+            recipe.hash = recipe_hash(&recipe);
+        }
+
+        Ok(recipe)
+    }
+}
+
+#[async_trait]
+impl RecipeBackend for LocalDirectory {
+    fn requires_credentials(&self) -> bool {
+        false
+    }
+
+    async fn login(&self, _account: &Account) -> BackendResult<String> {
+        if !self.root.is_dir() {
+            fs::create_dir_all(&self.root)?;
+        }
+        Ok(LOCAL_TOKEN.into())
+    }
+
+    async fn recipe_entries(&self, _token: &str) -> BackendResult<Vec<RecipeEntry>> {
+        let mut entries = Vec::new();
+
+        for dir_entry in fs::read_dir(&self.root)? {
+            let path = dir_entry?.path();
+
+            if path.extension().is_none_or(|extension| extension != "json")
+                || path.file_name().is_some_and(|name| name == CATEGORIES_FILE)
+            {
+                continue;
+            }
+
+            // another tool may be halfway through writing it; it's listed next time
+            if let Ok(recipe) = Self::read_recipe(&path) {
+                entries.push(RecipeEntry {
+                    uid: recipe.uid,
+                    hash: recipe.hash,
+                });
+            }
+        }
+
+        Ok(entries)
+    }
+
+    async fn recipe(&self, _token: &str, uid: &str) -> BackendResult<Recipe> {
+        Self::read_recipe(&self.recipe_path(uid))
+    }
+
+    async fn upload_recipe(&self, _token: &str, recipe: &mut Recipe) -> BackendResult<()> {
+        prepare_upload(recipe);
+
+        if !self.root.is_dir() {
+            fs::create_dir_all(&self.root)?;
+        }
+
+        fs::write(
+            self.recipe_path(&recipe.uid),
+            serde_json::to_string_pretty(recipe)?,
+        )?;
+
+        Ok(())
+    }
+
+    /// Keeps the file, marked trashed, so other tools sharing the folder see the deletion
+    async fn delete_recipe(&self, token: &str, uid: &str) -> BackendResult<()> {
+        let mut recipe = self.recipe(token, uid).await?;
+        recipe.in_trash = true;
+        self.upload_recipe(token, &mut recipe).await
+    }
+
+    async fn categories(&self, _token: &str) -> BackendResult<Vec<Category>> {
+        let path = self.root.join(CATEGORIES_FILE);
+
+        if !path.is_file() {
+            return Ok(Vec::new());
+        }
+
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    async fn upload_categories(&self, _token: &str, categories: &[Category]) -> BackendResult<()> {
+        if !self.root.is_dir() {
+            fs::create_dir_all(&self.root)?;
+        }
+
+        fs::write(
+            self.root.join(CATEGORIES_FILE),
+            serde_json::to_string_pretty(categories)?,
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::backend::mock_server;
+
+    #[tokio::test]
+    async fn deleting_moves_the_recipe_to_the_trash() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = LocalDirectory::new(dir.path());
+        let token = backend
+            .login(&Account::new("".into(), "".into()))
+            .await
+            .unwrap();
+
+        let mut recipe = mock_server::recipes().remove(0);
+        recipe.in_trash = false;
+        backend.upload_recipe(&token, &mut recipe).await.unwrap();
+        let before = recipe.hash.clone();
+
+        backend.delete_recipe(&token, &recipe.uid).await.unwrap();
+
+        let entries = backend.recipe_entries(&token).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_ne!(entries[0].hash, before);
+        assert!(backend.recipe(&token, &recipe.uid).await.unwrap().in_trash);
+    }
+}
@@ -0,0 +1,356 @@
+//! An in-process stand-in for the Paprika sync service, so tests can exercise the real HTTP
+//! client without a network connection.
+//!
+//! It understands the handful of endpoints Ancho uses, under both the `/api/v1` and `/api/v2`
+//! prefixes, and serves recipes and categories from `tests/fixtures/`.
+
+use super::{recipe_hash, PaprikaCloud};
+use crate::app::account::Account;
+use crate::app::category::Category;
+use crate::app::paprika::Paprika;
+
+use flate2::read::GzDecoder;
+use paprika_api::api::Recipe;
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+pub const EMAIL: &str = "cook@example.com";
+pub const PASSWORD: &str = "hunter2";
+const TOKEN: &str = "mock-token";
+
+const FIXTURE: &str = include_str!("../../../tests/fixtures/recipes.json");
+const CATEGORY_FIXTURE: &str = include_str!("../../../tests/fixtures/categories.json");
+
+struct State {
+    recipes: BTreeMap<String, Recipe>,
+    categories: Vec<Category>,
+    requests: Vec<String>,
+}
+
+pub struct MockServer {
+    address: SocketAddr,
+    state: Arc<Mutex<State>>,
+}
+
+struct Request {
+    method: String,
+    path: String,
+    headers: BTreeMap<String, String>,
+    body: Vec<u8>,
+}
+
+struct Response {
+    status: &'static str,
+    body: String,
+}
+
+impl Response {
+    fn ok(result: serde_json::Value) -> Self {
+        Self {
+            status: "200 OK",
+            body: json!({ "result": result }).to_string(),
+        }
+    }
+
+    fn error(status: &'static str, message: &str) -> Self {
+        Self {
+            status,
+            body: json!({ "error": { "code": 0, "message": message } }).to_string(),
+        }
+    }
+}
+
+/// The recipes in `tests/fixtures/`, as they are written there
+pub fn recipes() -> Vec<Recipe> {
+    serde_json::from_str(FIXTURE).expect("Recipe fixtures are malformed")
+}
+
+/// The categories in `tests/fixtures/`
+pub fn categories() -> Vec<Category> {
+    serde_json::from_str(CATEGORY_FIXTURE).expect("Category fixtures are malformed")
+}
+
+/// A client talking to `server`, already logged in as the fixture account
+pub async fn logged_in(server: &MockServer) -> Paprika {
+    let mut paprika =
+        Paprika::with_backend(Box::new(PaprikaCloud::with_base_url(&server.base_url())));
+    paprika
+        .log_in(&Account::new(EMAIL.into(), PASSWORD.into()))
+        .await
+        .expect("Failed to log in to the mock server");
+    paprika
+}
+
+impl MockServer {
+    /// Binds to a free localhost port and starts serving on the current tokio runtime
+    pub async fn start() -> Self {
+        let recipes = recipes()
+            .into_iter()
+            .map(|mut recipe| {
+                recipe.hash = recipe_hash(&recipe);
+                (recipe.uid.clone(), recipe)
+            })
+            .collect();
+
+        let categories = categories();
+
+        let state = Arc::new(Mutex::new(State {
+            recipes,
+            categories,
+            requests: Vec::new(),
+        }));
+
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind mock server");
+        let address = listener.local_addr().unwrap();
+
+        let server_state = state.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(handle_connection(stream, server_state.clone()));
+            }
+        });
+
+        Self { address, state }
+    }
+
+    /// The URL to hand to `PaprikaCloud::with_base_url`
+    pub fn base_url(&self) -> String {
+        format!("http://{}/api/v2", self.address)
+    }
+
+    pub fn recipe(&self, uid: &str) -> Option<Recipe> {
+        self.state.lock().unwrap().recipes.get(uid).cloned()
+    }
+
+    pub fn recipe_count(&self) -> usize {
+        self.state.lock().unwrap().recipes.len()
+    }
+
+    pub fn categories(&self) -> Vec<Category> {
+        self.state.lock().unwrap().categories.clone()
+    }
+
+    /// Adds or replaces a recipe as if another device had synced it
+    pub fn put_recipe(&self, mut recipe: Recipe) {
+        recipe.hash = recipe_hash(&recipe);
+        self.state
+            .lock()
+            .unwrap()
+            .recipes
+            .insert(recipe.uid.clone(), recipe);
+    }
+
+    /// Drops a recipe as if another device had deleted it
+    pub fn remove_recipe(&self, uid: &str) {
+        self.state.lock().unwrap().recipes.remove(uid);
+    }
+
+    /// How many requests have been made for paths ending in `suffix`
+    pub fn request_count(&self, suffix: &str) -> usize {
+        self.state
+            .lock()
+            .unwrap()
+            .requests
+            .iter()
+            .filter(|path| path.ends_with(suffix))
+            .count()
+    }
+}
+
+async fn handle_connection(mut stream: TcpStream, state: Arc<Mutex<State>>) {
+    let response = match read_request(&mut stream).await {
+        Some(request) => route(&request, &state),
+        None => Response::error("400 Bad Request", "Malformed request"),
+    };
+
+    let raw = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        response.body.len(),
+        response.body
+    );
+    let _ = stream.write_all(raw.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+async fn read_request(stream: &mut TcpStream) -> Option<Request> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+
+    let header_end = loop {
+        if let Some(end) = find(&buffer, b"\r\n\r\n") {
+            break end;
+        }
+        let read = stream.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            return None;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next()?.split(' ');
+    let method = request_line.next()?.to_string();
+    let path = request_line.next()?.to_string();
+
+    let headers: BTreeMap<String, String> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+        .collect();
+
+    let content_length: usize = headers
+        .get("content-length")
+        .and_then(|length| length.parse().ok())
+        .unwrap_or(0);
+
+    let mut body = buffer[header_end + 4..].to_vec();
+    while body.len() < content_length {
+        let read = stream.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            return None;
+        }
+        body.extend_from_slice(&chunk[..read]);
+    }
+
+    Some(Request {
+        method,
+        path,
+        headers,
+        body,
+    })
+}
+
+fn route(request: &Request, state: &Arc<Mutex<State>>) -> Response {
+    let mut state = state.lock().unwrap();
+    state.requests.push(request.path.clone());
+
+    let endpoint = match request
+        .path
+        .strip_prefix("/api/v2/")
+        .or_else(|| request.path.strip_prefix("/api/v1/"))
+    {
+        Some(endpoint) => endpoint.trim_end_matches('/'),
+        None => return Response::error("404 Not Found", "Unknown API version"),
+    };
+
+    if request.method == "POST" && endpoint == "account/login" {
+        let form = parse_form(&String::from_utf8_lossy(&request.body));
+        let email = form.get("email").map(String::as_str);
+        let password = form.get("password").map(String::as_str);
+
+        return if email == Some(EMAIL) && password == Some(PASSWORD) {
+            Response::ok(json!({ "token": TOKEN }))
+        } else {
+            Response::error("401 Unauthorized", "Invalid email or password")
+        };
+    }
+
+    let authorized = request
+        .headers
+        .get("authorization")
+        .is_some_and(|value| value == &format!("Bearer {}", TOKEN));
+    if !authorized {
+        return Response::error("401 Unauthorized", "Unrecognized client");
+    }
+
+    match (request.method.as_str(), endpoint) {
+        ("GET", "sync/recipes") => {
+            let entries: Vec<_> = state
+                .recipes
+                .values()
+                .map(|recipe| json!({ "uid": recipe.uid, "hash": recipe.hash }))
+                .collect();
+            Response::ok(json!(entries))
+        }
+        ("GET", _) if endpoint.starts_with("sync/recipe/") => {
+            let uid = &endpoint["sync/recipe/".len()..];
+            match state.recipes.get(uid) {
+                Some(recipe) => Response::ok(json!(recipe)),
+                None => Response::error("404 Not Found", "No such recipe"),
+            }
+        }
+        ("POST", _) if endpoint.starts_with("sync/recipe/") => {
+            match parse_upload::<Recipe>(request) {
+                Some(recipe) => {
+                    state.recipes.insert(recipe.uid.clone(), recipe);
+                    Response::ok(json!(true))
+                }
+                None => Response::error("400 Bad Request", "Couldn't read recipe upload"),
+            }
+        }
+        ("GET", "sync/categories") => Response::ok(json!(state.categories)),
+        ("POST", "sync/categories") => match parse_upload(request) {
+            Some(categories) => {
+                state.categories = categories;
+                Response::ok(json!(true))
+            }
+            None => Response::error("400 Bad Request", "Couldn't read category upload"),
+        },
+        _ => Response::error("404 Not Found", "Unknown endpoint"),
+    }
+}
+
+/// Pulls the gzipped JSON out of the multipart "data" field
+fn parse_upload<T: DeserializeOwned>(request: &Request) -> Option<T> {
+    let content_type = request.headers.get("content-type")?;
+    let boundary = format!("--{}", content_type.split("boundary=").nth(1)?);
+
+    let part_start = find(&request.body, boundary.as_bytes())?;
+    let data_start = part_start + find(&request.body[part_start..], b"\r\n\r\n")? + 4;
+    let data_length = find(
+        &request.body[data_start..],
+        format!("\r\n{}", boundary).as_bytes(),
+    )?;
+
+    let mut json = String::new();
+    GzDecoder::new(&request.body[data_start..data_start + data_length])
+        .read_to_string(&mut json)
+        .ok()?;
+
+    serde_json::from_str(&json).ok()
+}
+
+fn parse_form(body: &str) -> BTreeMap<String, String> {
+    body.split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(key, value)| (percent_decode(key), percent_decode(value)))
+        .collect()
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len() => match u8::from_str_radix(&value[i + 1..i + 3], 16) {
+                Ok(byte) => {
+                    decoded.push(byte);
+                    i += 2;
+                }
+                Err(_) => decoded.push(b'%'),
+            },
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
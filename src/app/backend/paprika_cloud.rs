@@ -0,0 +1,169 @@
+use super::{prepare_upload, BackendResult, RecipeBackend};
+use crate::app::account::Account;
+use crate::app::category::Category;
+use crate::app::error::Error;
+
+use async_trait::async_trait;
+use flate2::{write::GzEncoder, Compression};
+use paprika_api::api::{ApiResponse, ApiResult, Recipe, RecipeEntry};
+use reqwest::{header, multipart, StatusCode};
+use serde::Serialize;
+use std::io::Write;
+
+pub const DEFAULT_BASE_URL: &str = "https://www.paprikaapp.com/api/v2";
+
+/// The Paprika sync service, or anything that speaks its API
+pub struct PaprikaCloud {
+    base_url: String,
+    client: reqwest::Client,
+}
+
+impl PaprikaCloud {
+    pub fn new() -> Self {
+        Self::with_base_url(DEFAULT_BASE_URL)
+    }
+
+    /// Targets a Paprika-compatible server other than the official one, e.g. `http://localhost:8080/api/v2`
+    pub fn with_base_url(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').into(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    fn url(&self, endpoint: &str) -> String {
+        format!("{}/{}/", self.base_url, endpoint)
+    }
+
+    async fn parse_response(response: reqwest::Response) -> BackendResult<ApiResult> {
+        let status = response.status();
+        let url = response.url().to_string();
+        let body = response.text().await?;
+
+        if status == StatusCode::UNAUTHORIZED {
+            return Err(Error::Auth(format!("{} returned {}", url, status)));
+        }
+        if !status.is_success() {
+            return Err(Error::Network(format!(
+                "{} returned {}: {}",
+                url, status, body
+            )));
+        }
+
+        let response: ApiResponse = serde_json::from_str(&body)?;
+        Ok(response.result)
+    }
+
+    async fn get(&self, token: &str, endpoint: &str) -> BackendResult<ApiResult> {
+        let response = self
+            .client
+            .get(self.url(endpoint))
+            .bearer_auth(token)
+            .send()
+            .await?;
+
+        Self::parse_response(response).await
+    }
+
+    /// The sync API wants uploads as gzipped JSON, sent as a multipart file named "data"
+    async fn post_gzipped(
+        &self,
+        token: &str,
+        endpoint: &str,
+        body: &(impl Serialize + ?Sized),
+    ) -> BackendResult<ApiResult> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::none());
+        encoder.write_all(&serde_json::to_vec(body)?)?;
+        let part = multipart::Part::bytes(encoder.finish()?).file_name("data");
+        let form = multipart::Form::new().part("data", part);
+
+        let response = self
+            .client
+            .post(self.url(endpoint))
+            .multipart(form)
+            .header(header::ACCEPT, "*/*")
+            .bearer_auth(token)
+            .send()
+            .await?;
+
+        Self::parse_response(response).await
+    }
+}
+
+#[async_trait]
+impl RecipeBackend for PaprikaCloud {
+    async fn login(&self, account: &Account) -> BackendResult<String> {
+        let params = [
+            ("email", account.username.as_str()),
+            ("password", account.password.as_str()),
+        ];
+
+        let response = self
+            .client
+            .post(self.url("account/login"))
+            .form(&params)
+            .send()
+            .await?;
+
+        match Self::parse_response(response).await? {
+            ApiResult::Token(token) => Ok(token.token),
+            _ => Err(Error::Auth("the server didn't send back a token".into())),
+        }
+    }
+
+    async fn recipe_entries(&self, token: &str) -> BackendResult<Vec<RecipeEntry>> {
+        match self.get(token, "sync/recipes").await? {
+            ApiResult::Recipes(entries) => Ok(entries),
+            _ => Err(Error::Network("expected a recipe list".into())),
+        }
+    }
+
+    async fn recipe(&self, token: &str, uid: &str) -> BackendResult<Recipe> {
+        match self.get(token, &format!("sync/recipe/{}", uid)).await? {
+            ApiResult::Recipe(recipe) => Ok(recipe),
+            _ => Err(Error::Network(format!("expected recipe {}", uid))),
+        }
+    }
+
+    async fn upload_recipe(&self, token: &str, recipe: &mut Recipe) -> BackendResult<()> {
+        prepare_upload(recipe);
+
+        let endpoint = format!("sync/recipe/{}", recipe.uid);
+        match self.post_gzipped(token, &endpoint, recipe).await? {
+            ApiResult::Bool(true) => Ok(()),
+            _ => Err(Error::Network(format!(
+                "the server rejected recipe {}",
+                recipe.uid
+            ))),
+        }
+    }
+
+    /// Marks the recipe trashed the same way the Paprika apps do
+    async fn delete_recipe(&self, token: &str, uid: &str) -> BackendResult<()> {
+        let mut recipe = self.recipe(token, uid).await?;
+        recipe.in_trash = true;
+        self.upload_recipe(token, &mut recipe).await
+    }
+
+    async fn categories(&self, token: &str) -> BackendResult<Vec<Category>> {
+        match self.get(token, "sync/categories").await? {
+            ApiResult::Categories(categories) => {
+                Ok(categories.into_iter().map(Category::from).collect())
+            }
+            // an empty list can't be told apart from an empty recipe list
+            ApiResult::Recipes(entries) if entries.is_empty() => Ok(Vec::new()),
+            _ => Err(Error::Network("expected a category list".into())),
+        }
+    }
+
+    /// Sends the full category list back, the way the Paprika apps do
+    async fn upload_categories(&self, token: &str, categories: &[Category]) -> BackendResult<()> {
+        match self
+            .post_gzipped(token, "sync/categories", categories)
+            .await?
+        {
+            ApiResult::Bool(true) => Ok(()),
+            _ => Err(Error::Network("the server rejected the categories".into())),
+        }
+    }
+}
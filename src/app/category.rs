@@ -0,0 +1,203 @@
+use crate::app::error::Result;
+
+use paprika_api::api;
+use serde::{Deserialize, Serialize};
+use std::{fmt, fs, path};
+use uuid::Uuid;
+
+const CATEGORY_CACHE: &str = "categories.json";
+
+/// A recipe category; `parent_uid` nests it under another one
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Category {
+    pub uid: String,
+    pub name: String,
+    pub parent_uid: Option<String>,
+    pub order_flag: i32,
+}
+
+impl Category {
+    /// A category that doesn't exist in any store yet
+    pub fn new(name: String, parent_uid: Option<String>, order_flag: i32) -> Self {
+        Self {
+            uid: Uuid::new_v4().to_string().to_uppercase(),
+            name,
+            parent_uid,
+            order_flag,
+        }
+    }
+}
+
+impl From<api::Category> for Category {
+    fn from(category: api::Category) -> Self {
+        Self {
+            uid: category.uid,
+            name: category.name,
+            parent_uid: category.parent_uid,
+            order_flag: category.order_flag,
+        }
+    }
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// The display name of the category `uid`, or the uid itself if we don't know it
+pub fn name_of<'a>(categories: &'a [Category], uid: &'a str) -> &'a str {
+    categories
+        .iter()
+        .find(|category| category.uid == uid)
+        .map(|category| category.name.as_str())
+        .unwrap_or(uid)
+}
+
+/// Lays `categories` out as a tree, returning `(depth, index)` pairs in display order.
+///
+/// Siblings are ordered by `order_flag`, then name. Categories whose parent is missing are shown
+/// at the top level so nothing disappears.
+pub fn tree(categories: &[Category]) -> Vec<(usize, usize)> {
+    let is_root = |category: &Category| match &category.parent_uid {
+        Some(parent) => !categories.iter().any(|other| &other.uid == parent),
+        None => true,
+    };
+
+    let mut order = Vec::with_capacity(categories.len());
+    let mut visited = vec![false; categories.len()];
+    let roots = children(categories, |category| is_root(category));
+    for root in roots {
+        push_subtree(categories, root, 0, &mut order, &mut visited);
+    }
+
+    order
+}
+
+fn children(categories: &[Category], mut pick: impl FnMut(&Category) -> bool) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..categories.len())
+        .filter(|&i| pick(&categories[i]))
+        .collect();
+    indices.sort_by(|&a, &b| {
+        let (a, b) = (&categories[a], &categories[b]);
+        a.order_flag
+            .cmp(&b.order_flag)
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+    indices
+}
+
+fn push_subtree(
+    categories: &[Category],
+    index: usize,
+    depth: usize,
+    order: &mut Vec<(usize, usize)>,
+    visited: &mut Vec<bool>,
+) {
+    // a parent loop in the data would otherwise recurse forever
+    if visited[index] {
+        return;
+    }
+    visited[index] = true;
+    order.push((depth, index));
+
+    let uid = &categories[index].uid;
+    for child in children(categories, |category| {
+        category.parent_uid.as_ref() == Some(uid)
+    }) {
+        push_subtree(categories, child, depth + 1, order, visited);
+    }
+}
+
+/// Removes the category `uid`, moving its children up to its parent
+pub fn remove(categories: &mut Vec<Category>, uid: &str) {
+    let position = match categories.iter().position(|category| category.uid == uid) {
+        Some(position) => position,
+        None => return,
+    };
+    let removed = categories.remove(position);
+
+    for category in categories.iter_mut() {
+        if category.parent_uid.as_deref() == Some(uid) {
+            category.parent_uid = removed.parent_uid.clone();
+        }
+    }
+}
+
+pub fn cache_file(cache_dir: &path::Path) -> path::PathBuf {
+    cache_dir.join(CATEGORY_CACHE)
+}
+
+/// The categories saved by the last successful fetch, if any
+pub fn load_cached(cache_dir: &path::Path) -> Result<Vec<Category>> {
+    let path = cache_file(cache_dir);
+
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+pub fn save_to_cache(cache_dir: &path::Path, categories: &[Category]) -> Result<()> {
+    if !cache_dir.is_dir() {
+        fs::create_dir_all(cache_dir)?;
+    }
+
+    fs::write(cache_file(cache_dir), serde_json::to_string(categories)?)?;
+    Ok(())
+}
+
+pub fn clear_cache(cache_dir: &path::Path) -> Result<()> {
+    let path = cache_file(cache_dir);
+    if path.is_file() {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::backend::mock_server;
+
+    fn names(categories: &[Category]) -> Vec<(usize, &str)> {
+        tree(categories)
+            .into_iter()
+            .map(|(depth, i)| (depth, categories[i].name.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn tree_nests_children_under_parents() {
+        let categories = mock_server::categories();
+
+        assert_eq!(
+            names(&categories),
+            vec![(0, "Dinner"), (1, "Indian"), (0, "Dessert")]
+        );
+    }
+
+    #[test]
+    fn removing_a_parent_keeps_its_children() {
+        let mut categories = mock_server::categories();
+        let dinner = categories[0].uid.clone();
+
+        remove(&mut categories, &dinner);
+
+        assert_eq!(names(&categories), vec![(0, "Indian"), (0, "Dessert")]);
+    }
+
+    #[test]
+    fn cache_round_trip() {
+        let cache = tempfile::tempdir().unwrap();
+        assert!(load_cached(cache.path()).unwrap().is_empty());
+
+        save_to_cache(cache.path(), &mock_server::categories()).unwrap();
+
+        assert_eq!(
+            load_cached(cache.path()).unwrap(),
+            mock_server::categories()
+        );
+    }
+}
@@ -0,0 +1,329 @@
+use crate::app::category::{self, Category};
+use crate::app::theme::Theme;
+use crate::app::Message;
+
+use iced::{
+    button, pane_grid, pick_list, scrollable, text_input, Align, Button, Element, Length, PickList,
+    Row, Scrollable, Space, Text, TextInput,
+};
+use std::collections::HashMap;
+use std::fmt;
+
+const INDENT: u16 = 20;
+
+/// Where a new category goes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParentChoice {
+    TopLevel,
+    Under(Category, usize),
+}
+
+impl fmt::Display for ParentChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParentChoice::TopLevel => write!(f, "Top level"),
+            ParentChoice::Under(category, depth) => {
+                write!(f, "{}{}", "  ".repeat(*depth), category.name)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum CategoryMessage {
+    /// Category uid and its new name
+    Renamed(String, String),
+    Deleted(String),
+    NewNameChanged(String),
+    NewParentChanged(ParentChoice),
+    Added,
+}
+
+struct CategoryRow {
+    input: text_input::State,
+    delete: button::State,
+}
+
+impl CategoryRow {
+    fn new() -> Self {
+        Self {
+            input: text_input::State::new(),
+            delete: button::State::new(),
+        }
+    }
+}
+
+/// Creates, renames and deletes categories. Changes stay local until saved.
+pub struct CategoryManager {
+    categories: Vec<Category>,
+    /// Uids of categories deleted since the manager was opened
+    deleted: Vec<String>,
+    /// The names categories had when the manager was opened, or were added with, by uid
+    original_names: HashMap<String, String>,
+    new_name: String,
+    new_parent: Option<String>,
+    pub saving: bool,
+
+    scroll: scrollable::State,
+    rows: Vec<CategoryRow>,
+    new_input: text_input::State,
+    parent_picker: pick_list::State<ParentChoice>,
+    add: button::State,
+    save: button::State,
+    cancel: button::State,
+}
+
+impl CategoryManager {
+    pub fn new(categories: Vec<Category>) -> Self {
+        Self {
+            rows: categories.iter().map(|_| CategoryRow::new()).collect(),
+            original_names: categories
+                .iter()
+                .map(|category| (category.uid.clone(), category.name.clone()))
+                .collect(),
+            categories,
+            deleted: Vec::new(),
+            new_name: String::new(),
+            new_parent: None,
+            saving: false,
+            scroll: scrollable::State::new(),
+            new_input: text_input::State::new(),
+            parent_picker: pick_list::State::default(),
+            add: button::State::new(),
+            save: button::State::new(),
+            cancel: button::State::new(),
+        }
+    }
+
+    pub fn update(&mut self, message: CategoryMessage) {
+        match message {
+            CategoryMessage::Renamed(uid, name) => {
+                if let Some(category) = self.categories.iter_mut().find(|c| c.uid == uid) {
+                    category.name = name;
+                }
+            }
+            CategoryMessage::Deleted(uid) => {
+                if let Some(i) = self.categories.iter().position(|c| c.uid == uid) {
+                    self.rows.remove(i);
+                }
+                category::remove(&mut self.categories, &uid);
+                if self.new_parent.as_ref() == Some(&uid) {
+                    self.new_parent = None;
+                }
+                self.deleted.push(uid);
+            }
+            CategoryMessage::NewNameChanged(name) => self.new_name = name,
+            CategoryMessage::NewParentChanged(ParentChoice::TopLevel) => self.new_parent = None,
+            CategoryMessage::NewParentChanged(ParentChoice::Under(parent, _)) => {
+                self.new_parent = Some(parent.uid)
+            }
+            CategoryMessage::Added => {
+                let name = self.new_name.trim();
+                if name.is_empty() {
+                    return;
+                }
+
+                let order_flag = self
+                    .categories
+                    .iter()
+                    .filter(|category| category.parent_uid == self.new_parent)
+                    .map(|category| category.order_flag + 1)
+                    .max()
+                    .unwrap_or(0);
+                let category = Category::new(name.into(), self.new_parent.clone(), order_flag);
+                // a new category cleared before saving falls back to the name it was added with
+                self.original_names
+                    .insert(category.uid.clone(), category.name.clone());
+                self.categories.push(category);
+                self.rows.push(CategoryRow::new());
+                self.new_name.clear();
+            }
+        }
+    }
+
+    /// The edited category list, and the uids of categories that were deleted from it. A
+    /// category whose name was cleared keeps its old name; only Delete removes one.
+    pub fn changes(&self) -> (Vec<Category>, Vec<String>) {
+        let categories = self
+            .categories
+            .iter()
+            .map(|category| {
+                let name = match category.name.trim() {
+                    "" => self
+                        .original_names
+                        .get(&category.uid)
+                        .map_or("", |name| name.trim()),
+                    name => name,
+                };
+                Category {
+                    name: name.into(),
+                    ..category.clone()
+                }
+            })
+            .collect();
+
+        (categories, self.deleted.clone())
+    }
+
+    pub fn view(&mut self, pane: pane_grid::Pane, theme: Theme) -> Element<'_, Message> {
+        let order = category::tree(&self.categories);
+        let parent_choices: Vec<ParentChoice> = std::iter::once(ParentChoice::TopLevel)
+            .chain(
+                order
+                    .iter()
+                    .map(|&(depth, i)| ParentChoice::Under(self.categories[i].clone(), depth)),
+            )
+            .collect();
+        let selected_parent = parent_choices
+            .iter()
+            .find(|choice| match (choice, &self.new_parent) {
+                (ParentChoice::Under(category, _), Some(uid)) => &category.uid == uid,
+                (ParentChoice::TopLevel, None) => true,
+                _ => false,
+            })
+            .cloned();
+
+        let mut content = Scrollable::new(&mut self.scroll)
+            .style(theme)
+            .width(Length::Fill)
+            .padding(10)
+            .spacing(5)
+            .push(Text::new("Categories").size(28));
+
+        // rows are kept in the same order as `categories`, so pair them up before reordering
+        let mut rows: Vec<Option<(&Category, &mut CategoryRow)>> = self
+            .categories
+            .iter()
+            .zip(self.rows.iter_mut())
+            .map(Some)
+            .collect();
+        for (depth, i) in order {
+            let (category, row) = match rows[i].take() {
+                Some(pair) => pair,
+                None => continue,
+            };
+            let uid = category.uid.clone();
+            let deleted = category.uid.clone();
+
+            content = content.push(
+                Row::new()
+                    .spacing(5)
+                    .align_items(Align::Center)
+                    .push(Space::with_width(Length::Units(INDENT * depth as u16)))
+                    .push(
+                        TextInput::new(&mut row.input, "Name", &category.name, move |name| {
+                            Message::CategoryManager(
+                                pane,
+                                CategoryMessage::Renamed(uid.clone(), name),
+                            )
+                        })
+                        .style(theme)
+                        .padding(5),
+                    )
+                    .push(
+                        Button::new(&mut row.delete, Text::new("Delete"))
+                            .style(theme)
+                            .on_press(Message::CategoryManager(
+                                pane,
+                                CategoryMessage::Deleted(deleted),
+                            )),
+                    ),
+            );
+        }
+
+        content = content.push(
+            Row::new()
+                .spacing(5)
+                .align_items(Align::Center)
+                .push(
+                    TextInput::new(
+                        &mut self.new_input,
+                        "New category",
+                        &self.new_name,
+                        move |name| {
+                            Message::CategoryManager(pane, CategoryMessage::NewNameChanged(name))
+                        },
+                    )
+                    .style(theme)
+                    .on_submit(Message::CategoryManager(pane, CategoryMessage::Added))
+                    .padding(5),
+                )
+                .push(
+                    PickList::new(
+                        &mut self.parent_picker,
+                        parent_choices,
+                        selected_parent,
+                        move |parent| {
+                            Message::CategoryManager(
+                                pane,
+                                CategoryMessage::NewParentChanged(parent),
+                            )
+                        },
+                    )
+                    .style(theme),
+                )
+                .push(
+                    Button::new(&mut self.add, Text::new("Add"))
+                        .style(theme)
+                        .on_press(Message::CategoryManager(pane, CategoryMessage::Added)),
+                ),
+        );
+
+        let mut save = Button::new(
+            &mut self.save,
+            Text::new(if self.saving { "Saving…" } else { "Save" }),
+        )
+        .style(theme);
+        if !self.saving {
+            save = save.on_press(Message::CategoriesSaveClicked(pane));
+        }
+        content = content.push(
+            Row::new().spacing(10).push(save).push(
+                Button::new(&mut self.cancel, Text::new("Cancel"))
+                    .style(theme)
+                    .on_press(Message::Close(pane)),
+            ),
+        );
+
+        content.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::backend::mock_server;
+
+    #[test]
+    fn edits_stay_local_until_collected() {
+        let categories = mock_server::categories();
+        let dinner = categories[0].uid.clone();
+        let indian = categories[1].uid.clone();
+        let dessert = categories[2].uid.clone();
+        let mut manager = CategoryManager::new(categories);
+
+        manager.update(CategoryMessage::Renamed(dessert, " Sweets ".into()));
+        // clearing a name doesn't drop the category
+        manager.update(CategoryMessage::Renamed(indian, "  ".into()));
+        manager.update(CategoryMessage::Deleted(dinner.clone()));
+        manager.update(CategoryMessage::NewNameChanged("Breakfast".into()));
+        manager.update(CategoryMessage::Added);
+
+        let (categories, deleted) = manager.changes();
+        let names: Vec<&str> = categories.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["Indian", "Sweets", "Breakfast"]);
+        assert!(categories.iter().all(|c| c.parent_uid.is_none()));
+        assert_eq!(deleted, vec![dinner]);
+    }
+
+    #[test]
+    fn new_categories_keep_their_name_when_cleared() {
+        let mut manager = CategoryManager::new(Vec::new());
+        manager.update(CategoryMessage::NewNameChanged("Breakfast".into()));
+        manager.update(CategoryMessage::Added);
+        let uid = manager.changes().0[0].uid.clone();
+
+        manager.update(CategoryMessage::Renamed(uid, String::new()));
+        assert_eq!(manager.changes().0[0].name, "Breakfast");
+    }
+}
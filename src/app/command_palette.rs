@@ -0,0 +1,224 @@
+use crate::app::recipe_filter;
+use crate::app::shortcuts::{Action, Shortcuts};
+use crate::app::theme::Theme;
+use crate::app::Message;
+
+use iced::{
+    button, text_input, Align, Button, Column, Container, Element, Length, Row, Text, TextInput,
+};
+use paprika_api::api::Recipe;
+
+const MAX_RESULTS: usize = 8;
+
+/// Something the palette can run
+#[derive(Debug, Clone, PartialEq)]
+pub enum PaletteEntry {
+    Action(Action),
+    /// A recipe uid, to open
+    Recipe(String),
+}
+
+#[derive(Debug, Clone)]
+pub enum PaletteMessage {
+    QueryChanged(String),
+    Submitted,
+}
+
+/// One line of the palette
+pub struct PaletteResult {
+    pub entry: PaletteEntry,
+    label: String,
+    shortcut: String,
+}
+
+/// Finds any action or recipe by typing part of its name
+pub struct CommandPalette {
+    query: String,
+    /// Which result Enter runs
+    selected: usize,
+
+    input: text_input::State,
+    result_buttons: Vec<button::State>,
+}
+
+impl CommandPalette {
+    pub fn new() -> Self {
+        Self {
+            query: String::new(),
+            selected: 0,
+            input: text_input::State::focused(),
+            result_buttons: Vec::new(),
+        }
+    }
+
+    pub fn update(&mut self, message: PaletteMessage) {
+        match message {
+            PaletteMessage::QueryChanged(query) => {
+                self.query = query;
+                self.selected = 0;
+            }
+            PaletteMessage::Submitted => (),
+        }
+    }
+
+    /// Moves the selection up or down by `by`, stopping at either end
+    pub fn select(&mut self, by: isize, results: usize) {
+        let last = results.saturating_sub(1) as isize;
+        self.selected = (self.selected as isize + by).clamp(0, last.max(0)) as usize;
+    }
+
+    /// The best matches for what's been typed, best first. With nothing typed, every action is
+    /// listed; recipes only show up once searched for.
+    pub fn results(&self, recipes: &[Recipe], shortcuts: &Shortcuts) -> Vec<PaletteResult> {
+        let actions = Action::ALL.iter().map(|action| PaletteResult {
+            entry: PaletteEntry::Action(*action),
+            label: action.to_string(),
+            shortcut: shortcuts.shortcut(*action).to_string(),
+        });
+        let query = self.query.trim();
+        if query.is_empty() {
+            return actions.collect();
+        }
+
+        let recipes = recipes
+            .iter()
+            .filter(|recipe| !recipe.in_trash)
+            .map(|recipe| PaletteResult {
+                entry: PaletteEntry::Recipe(recipe.uid.clone()),
+                label: recipe.name.clone(),
+                shortcut: String::new(),
+            });
+        let mut scored: Vec<(i32, PaletteResult)> = actions
+            .chain(recipes)
+            .filter_map(|result| Some((fuzzy_score(query, &result.label)?, result)))
+            .collect();
+        scored.sort_by(|(a_score, a), (b_score, b)| {
+            b_score
+                .cmp(a_score)
+                .then(a.label.len().cmp(&b.label.len()))
+                .then_with(|| a.label.cmp(&b.label))
+        });
+        scored
+            .into_iter()
+            .take(MAX_RESULTS)
+            .map(|(_, result)| result)
+            .collect()
+    }
+
+    /// The entry Enter runs, out of `results`
+    pub fn selected<'a>(&self, results: &'a [PaletteResult]) -> Option<&'a PaletteEntry> {
+        results
+            .get(self.selected)
+            .or_else(|| results.first())
+            .map(|result| &result.entry)
+    }
+
+    pub fn view(&mut self, results: Vec<PaletteResult>, theme: Theme) -> Element<'_, Message> {
+        let input = TextInput::new(
+            &mut self.input,
+            "Type a command or recipe name",
+            &self.query,
+            |query| Message::CommandPalette(PaletteMessage::QueryChanged(query)),
+        )
+        .style(theme)
+        .padding(5)
+        .on_submit(Message::CommandPalette(PaletteMessage::Submitted));
+
+        let mut column = Column::new().spacing(2).push(input);
+        if results.is_empty() {
+            column = column.push(Text::new("Nothing matches").size(16));
+        }
+
+        self.result_buttons
+            .resize_with(results.len(), button::State::new);
+        let selected = self.selected.min(results.len().saturating_sub(1));
+        for (i, (result, state)) in results
+            .into_iter()
+            .zip(self.result_buttons.iter_mut())
+            .enumerate()
+        {
+            let marker = if i == selected { "› " } else { "" };
+            let row = Row::new()
+                .spacing(10)
+                .align_items(Align::Center)
+                .push(Text::new(format!("{}{}", marker, result.label)).width(Length::Fill))
+                .push(Text::new(result.shortcut).size(14));
+            column = column.push(
+                Button::new(state, row)
+                    .style(theme)
+                    .width(Length::Fill)
+                    .on_press(Message::PaletteEntryPicked(result.entry)),
+            );
+        }
+
+        Container::new(column).width(Length::Fill).padding(5).into()
+    }
+}
+
+/// How well `query` matches `text`, or `None` if it doesn't at all. Every character typed has to
+/// appear in order, ignoring case and accents; runs of them and word starts count for more, and
+/// gaps count against.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let query: Vec<char> = recipe_filter::fold(query)
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    let text: Vec<char> = recipe_filter::fold(text).chars().collect();
+
+    let mut score = 0;
+    let mut next = 0;
+    let mut last_match: Option<usize> = None;
+    for wanted in query {
+        let found = (next..text.len()).find(|&i| text[i] == wanted)?;
+        let at_word_start = found == 0 || !text[found - 1].is_alphanumeric();
+        score += 1;
+        if at_word_start {
+            score += 3;
+        }
+        match last_match {
+            Some(last) if found == last + 1 => score += 5,
+            Some(last) => score -= (found - last - 1) as i32,
+            None => score -= found as i32,
+        }
+        last_match = Some(found);
+        next = found + 1;
+    }
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::backend::mock_server;
+
+    #[test]
+    fn close_matches_come_first() {
+        assert_eq!(fuzzy_score("xyz", "Sync now"), None);
+        assert!(fuzzy_score("sn", "Sync now") > fuzzy_score("sn", "Show shopping list"));
+        assert!(fuzzy_score("chm", "Chana masala") > fuzzy_score("chm", "Chocolate mousse"));
+        assert!(fuzzy_score("creme", "Crème brûlée").is_some());
+
+        let recipes = mock_server::recipes();
+        let mut palette = CommandPalette::new();
+        let shortcuts = Shortcuts::default();
+        assert_eq!(
+            palette.results(&recipes, &shortcuts).len(),
+            Action::ALL.len()
+        );
+
+        palette.update(PaletteMessage::QueryChanged("sync".into()));
+        let results = palette.results(&recipes, &shortcuts);
+        assert_eq!(
+            palette.selected(&results),
+            Some(&PaletteEntry::Action(Action::SyncNow))
+        );
+
+        let recipe = recipes.iter().find(|recipe| !recipe.in_trash).unwrap();
+        palette.update(PaletteMessage::QueryChanged(recipe.name.to_uppercase()));
+        let results = palette.results(&recipes, &shortcuts);
+        assert_eq!(
+            palette.selected(&results),
+            Some(&PaletteEntry::Recipe(recipe.uid.clone()))
+        );
+    }
+}
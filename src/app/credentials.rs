@@ -0,0 +1,142 @@
+//! The login token, kept between runs in a file encrypted with a passphrase of the user's
+//! choosing. Passwords are never written anywhere; once the token is unlocked it's all the app
+//! needs to talk to the server.
+//!
+//! The passphrase is stretched into a key with Argon2id and the token sealed with
+//! ChaCha20-Poly1305, so a wrong passphrase is caught rather than producing garbage.
+
+use crate::app::error::{Error, Result};
+
+use argon2::Argon2;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+use std::{fs, io::Write, path};
+use zeroize::Zeroize;
+
+const CREDENTIALS_FILE: &str = "credentials.json";
+const SALT_LEN: usize = 16;
+
+pub fn credentials_file(data_dir: &path::Path) -> path::PathBuf {
+    data_dir.join(CREDENTIALS_FILE)
+}
+
+/// The file's contents, all hex-encoded
+#[derive(Serialize, Deserialize)]
+struct SealedToken {
+    salt: String,
+    nonce: String,
+    token: String,
+}
+
+fn key(passphrase: &str, salt: &[u8]) -> Result<Key> {
+    let mut key = Key::default();
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| Error::Credentials(e.to_string()))?;
+    Ok(key)
+}
+
+/// Encrypts `token` with `passphrase` and writes it to `path`, readable only by the current user
+pub fn save(path: &path::Path, token: &str, passphrase: &str) -> Result<()> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let mut key = key(passphrase, &salt)?;
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let sealed = ChaCha20Poly1305::new(&key).encrypt(&nonce, token.as_bytes());
+    key.zeroize();
+    let sealed = sealed.map_err(|_| Error::Credentials("encryption failed".into()))?;
+
+    let contents = serde_json::to_string(&SealedToken {
+        salt: hex::encode(salt),
+        nonce: hex::encode(nonce),
+        token: hex::encode(sealed),
+    })?;
+
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path)?;
+    // the mode only applies to a new file, so one left readable by others is tightened too
+    #[cfg(unix)]
+    file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+    file.write_all(contents.as_bytes())?;
+    Ok(())
+}
+
+/// Decrypts the token saved at `path`
+pub fn load(path: &path::Path, passphrase: &str) -> Result<String> {
+    let contents = fs::read_to_string(path)?;
+    let sealed: SealedToken = serde_json::from_str(&contents)?;
+    let unhex = |field: &str| {
+        hex::decode(field).map_err(|_| Error::Credentials(format!("{} is damaged", path.display())))
+    };
+    let (salt, nonce, token) = (
+        unhex(&sealed.salt)?,
+        unhex(&sealed.nonce)?,
+        unhex(&sealed.token)?,
+    );
+    if nonce.len() != 12 {
+        return Err(Error::Credentials(format!("{} is damaged", path.display())));
+    }
+
+    let mut key = key(passphrase, &salt)?;
+    let token = ChaCha20Poly1305::new(&key).decrypt(Nonce::from_slice(&nonce), token.as_ref());
+    key.zeroize();
+    let token = token.map_err(|_| Error::Credentials("wrong passphrase".into()))?;
+    String::from_utf8(token)
+        .map_err(|_| Error::Credentials(format!("{} is damaged", path.display())))
+}
+
+/// Forgets the saved token, if there is one
+pub fn remove(path: &path::Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_only_unlocks_with_its_passphrase() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = credentials_file(&dir.path().join("ancho"));
+
+        save(&path, "secret-token", "correct horse").unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(!contents.contains("secret-token"));
+
+        assert_eq!(load(&path, "correct horse").unwrap(), "secret-token");
+        assert!(matches!(
+            load(&path, "battery staple"),
+            Err(Error::Credentials(_))
+        ));
+
+        remove(&path).unwrap();
+        assert!(!path.exists());
+        remove(&path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rewriting_the_token_keeps_it_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = credentials_file(dir.path());
+        save(&path, "old-token", "correct horse").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+
+        save(&path, "new-token", "correct horse").unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
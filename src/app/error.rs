@@ -0,0 +1,79 @@
+use std::fmt;
+
+/// Everything that can go wrong while talking to a backend or touching local files.
+///
+/// Details are kept as strings so errors can be cloned into a `Message` and shown in the UI.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// The backend rejected our credentials or token
+    Auth(String),
+    /// There's no token and nothing to log in with
+    LoggedOut,
+    /// The saved login couldn't be read, written or unlocked
+    Credentials(String),
+    /// The backend couldn't be reached or sent back something unexpected
+    Network(String),
+    /// A recipe or API response couldn't be (de)serialized
+    Parse(String),
+    /// Reading or writing recipe files on disk failed
+    CacheIo(String),
+    /// A link couldn't be handed off to the system browser
+    Launch(String),
+    /// The settings file couldn't be read or written
+    Settings(String),
+    /// The local recipe database couldn't be opened, read or written
+    Database(String),
+    /// A recipe (named here) was changed elsewhere while we edited it
+    Conflict(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Auth(details) => write!(f, "Couldn't log in: {}", details),
+            Error::LoggedOut => write!(f, "Log in to sync your recipes"),
+            Error::Credentials(details) => write!(f, "Couldn't use the saved login: {}", details),
+            Error::Network(details) => write!(f, "Couldn't reach the recipe server: {}", details),
+            Error::Parse(details) => write!(f, "Couldn't read recipe: {}", details),
+            Error::CacheIo(details) => write!(f, "Couldn't access recipe files: {}", details),
+            Error::Launch(details) => write!(f, "Couldn't open the link: {}", details),
+            Error::Settings(details) => write!(f, "Couldn't access settings: {}", details),
+            Error::Database(details) => {
+                write!(f, "Couldn't access the recipe database: {}", details)
+            }
+            Error::Conflict(name) => write!(
+                f,
+                "{} was changed on another device too; choose which edits to keep",
+                name
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::Network(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Parse(e.to_string())
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::CacheIo(e.to_string())
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(e: rusqlite::Error) -> Self {
+        Error::Database(e.to_string())
+    }
+}
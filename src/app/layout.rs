@@ -0,0 +1,214 @@
+//! The arrangement of panes, saved between sessions as JSON next to `settings.toml`.
+//!
+//! Only panes that can be rebuilt from scratch are kept: navigation, recipe lists, open recipes,
+//! the shopping list and the trash. Forms and editors are dropped, and their neighbours take
+//! their space.
+
+use crate::app::error::{Error, Result};
+
+use iced::pane_grid::{Axis, Configuration, Node, Pane};
+use serde::{Deserialize, Serialize};
+use std::{fs, path};
+
+const LAYOUT_FILE: &str = "layout.json";
+/// How much of the window the nav pane takes to begin with
+pub const NAV_RATIO: f32 = 0.15;
+
+pub fn layout_file(config_dir: &path::Path) -> path::PathBuf {
+    config_dir.join(LAYOUT_FILE)
+}
+
+/// A pane worth bringing back next time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SavedPane {
+    Nav,
+    List,
+    /// An open recipe, by uid
+    Detail {
+        uid: String,
+    },
+    ShoppingList,
+    Trash,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Layout {
+    Split {
+        /// Side by side rather than one above the other
+        vertical: bool,
+        ratio: f32,
+        a: Box<Layout>,
+        b: Box<Layout>,
+    },
+    Pane(SavedPane),
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self::new(NAV_RATIO)
+    }
+}
+
+impl Layout {
+    /// The nav pane next to a recipe list, taking `nav_ratio` of the window
+    pub fn new(nav_ratio: f32) -> Self {
+        Layout::Split {
+            vertical: true,
+            ratio: nav_ratio,
+            a: Box::new(Layout::Pane(SavedPane::Nav)),
+            b: Box::new(Layout::Pane(SavedPane::List)),
+        }
+    }
+
+    /// Reads the layout saved at `path`, if there is one
+    pub fn load(path: &path::Path) -> Result<Option<Self>> {
+        if !path.is_file() {
+            return Ok(None);
+        }
+
+        let contents = fs::read_to_string(path)
+            .map_err(|e| Error::Settings(format!("{}: {}", path.display(), e)))?;
+        let layout = serde_json::from_str(&contents)
+            .map_err(|e| Error::Settings(format!("{}: {}", path.display(), e)))?;
+        Ok(Some(layout))
+    }
+
+    pub fn save(&self, path: &path::Path) -> Result<()> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)
+                .map_err(|e| Error::Settings(format!("{}: {}", dir.display(), e)))?;
+        }
+
+        let contents = serde_json::to_string_pretty(self)?;
+        fs::write(path, contents).map_err(|e| Error::Settings(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Records `node`, asking `saved` what each pane holds. Panes it returns `None` for are left
+    /// out, along with any split they leave empty.
+    pub fn capture(node: &Node, saved: &impl Fn(&Pane) -> Option<SavedPane>) -> Option<Self> {
+        match node {
+            Node::Pane(pane) => saved(pane).map(Layout::Pane),
+            Node::Split {
+                axis, ratio, a, b, ..
+            } => match (Self::capture(a, saved), Self::capture(b, saved)) {
+                (Some(a), Some(b)) => Some(Layout::Split {
+                    vertical: *axis == Axis::Vertical,
+                    ratio: *ratio,
+                    a: Box::new(a),
+                    b: Box::new(b),
+                }),
+                (a, b) => a.or(b),
+            },
+        }
+    }
+
+    /// Whether the layout has a pane matching `predicate`
+    pub fn contains(&self, predicate: &impl Fn(&SavedPane) -> bool) -> bool {
+        match self {
+            Layout::Split { a, b, .. } => a.contains(predicate) || b.contains(predicate),
+            Layout::Pane(pane) => predicate(pane),
+        }
+    }
+
+    /// Rebuilds the panes with `restore`, which can turn down panes that no longer make sense
+    /// (say, a recipe that has since been deleted)
+    pub fn into_configuration<T>(
+        self,
+        restore: &mut impl FnMut(SavedPane) -> Option<T>,
+    ) -> Option<Configuration<T>> {
+        match self {
+            Layout::Pane(pane) => restore(pane).map(Configuration::Pane),
+            Layout::Split {
+                vertical,
+                ratio,
+                a,
+                b,
+            } => {
+                let a = a.into_configuration(restore);
+                let b = b.into_configuration(restore);
+                match (a, b) {
+                    (Some(a), Some(b)) => Some(Configuration::Split {
+                        axis: if vertical {
+                            Axis::Vertical
+                        } else {
+                            Axis::Horizontal
+                        },
+                        ratio: ratio.clamp(0.0, 1.0),
+                        a: Box::new(a),
+                        b: Box::new(b),
+                    }),
+                    (a, b) => a.or(b),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iced::pane_grid::State;
+
+    fn detail(uid: &str) -> Layout {
+        Layout::Pane(SavedPane::Detail { uid: uid.into() })
+    }
+
+    fn split(ratio: f32, a: Layout, b: Layout) -> Layout {
+        Layout::Split {
+            vertical: true,
+            ratio,
+            a: Box::new(a),
+            b: Box::new(b),
+        }
+    }
+
+    #[test]
+    fn layouts_survive_a_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = layout_file(dir.path());
+        assert_eq!(Layout::load(&path).unwrap(), None);
+
+        // the list and two recipes side by side
+        let layout = split(
+            0.15,
+            Layout::Pane(SavedPane::Nav),
+            split(
+                0.4,
+                Layout::Pane(SavedPane::List),
+                split(0.5, detail("dal"), detail("chana-masala")),
+            ),
+        );
+        let state = State::with_configuration(
+            layout
+                .clone()
+                .into_configuration(&mut |pane| Some(pane))
+                .unwrap(),
+        );
+        let captured = Layout::capture(state.layout(), &|pane| state.get(pane).cloned()).unwrap();
+        assert_eq!(captured, layout);
+
+        captured.save(&path).unwrap();
+        assert_eq!(Layout::load(&path).unwrap(), Some(layout));
+    }
+
+    #[test]
+    fn panes_that_cant_be_kept_give_up_their_space() {
+        let layout = split(
+            0.15,
+            Layout::Pane(SavedPane::Nav),
+            split(0.5, Layout::Pane(SavedPane::List), detail("deleted")),
+        );
+
+        let restored = layout
+            .into_configuration(&mut |pane| match pane {
+                SavedPane::Detail { .. } => None,
+                pane => Some(pane),
+            })
+            .unwrap();
+        let state = State::with_configuration(restored);
+        let captured = Layout::capture(state.layout(), &|pane| state.get(pane).cloned()).unwrap();
+        assert_eq!(captured, Layout::default());
+    }
+}
@@ -0,0 +1,197 @@
+use crate::app::account::Account;
+use crate::app::theme::Theme;
+use crate::app::Message;
+
+use iced::{
+    button, pane_grid, text_input, Align, Button, Column, Element, Length, Row, Text, TextInput,
+};
+use zeroize::Zeroizing;
+
+const LABEL_WIDTH: u16 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LoginField {
+    Email,
+    Password,
+    Passphrase,
+}
+
+#[derive(Debug, Clone)]
+pub enum LoginMessage {
+    FieldChanged(LoginField, String),
+    /// Gives up on the saved login and asks for the password instead
+    UsePasswordClicked,
+}
+
+/// What a submitted form asks for
+pub enum LoginRequest {
+    /// Log in, remembering the token under the passphrase if there is one
+    Password(Account, Option<Zeroizing<String>>),
+    /// Unlock the token saved last time
+    Unlock(Zeroizing<String>),
+}
+
+/// Asks for an email and password, or just the passphrase when a login was saved last time
+pub struct LoginForm {
+    unlocking: bool,
+    email: String,
+    password: Zeroizing<String>,
+    passphrase: Zeroizing<String>,
+    pub busy: bool,
+
+    email_input: text_input::State,
+    password_input: text_input::State,
+    passphrase_input: text_input::State,
+    submit: button::State,
+    use_password: button::State,
+    cancel: button::State,
+}
+
+fn labelled<'a>(label: &str, input: TextInput<'a, Message>) -> Row<'a, Message> {
+    Row::new()
+        .spacing(10)
+        .align_items(Align::Center)
+        .push(Text::new(label).width(Length::Units(LABEL_WIDTH)))
+        .push(input)
+}
+
+impl LoginForm {
+    pub fn log_in() -> Self {
+        Self {
+            unlocking: false,
+            email: String::new(),
+            password: Zeroizing::new(String::new()),
+            passphrase: Zeroizing::new(String::new()),
+            busy: false,
+            email_input: text_input::State::focused(),
+            password_input: text_input::State::new(),
+            passphrase_input: text_input::State::new(),
+            submit: button::State::new(),
+            use_password: button::State::new(),
+            cancel: button::State::new(),
+        }
+    }
+
+    pub fn unlock() -> Self {
+        Self {
+            unlocking: true,
+            email_input: text_input::State::new(),
+            passphrase_input: text_input::State::focused(),
+            ..Self::log_in()
+        }
+    }
+
+    pub fn update(&mut self, message: LoginMessage) {
+        match message {
+            LoginMessage::FieldChanged(LoginField::Email, value) => self.email = value,
+            LoginMessage::FieldChanged(LoginField::Password, value) => {
+                self.password = Zeroizing::new(value)
+            }
+            LoginMessage::FieldChanged(LoginField::Passphrase, value) => {
+                self.passphrase = Zeroizing::new(value)
+            }
+            LoginMessage::UsePasswordClicked => *self = Self::log_in(),
+        }
+    }
+
+    /// Hands over what was typed in, clearing the secrets from the form
+    pub fn submit(&mut self) -> LoginRequest {
+        self.busy = true;
+        let passphrase = std::mem::take(&mut self.passphrase);
+        if self.unlocking {
+            return LoginRequest::Unlock(passphrase);
+        }
+
+        let account = Account::new(self.email.clone(), std::mem::take(&mut *self.password));
+        let passphrase = Some(passphrase).filter(|passphrase| !passphrase.is_empty());
+        LoginRequest::Password(account, passphrase)
+    }
+
+    pub fn view(&mut self, pane: pane_grid::Pane, theme: Theme) -> Element<'_, Message> {
+        let LoginForm {
+            unlocking,
+            email,
+            password,
+            passphrase,
+            busy,
+            email_input,
+            password_input,
+            passphrase_input,
+            submit,
+            use_password,
+            cancel,
+        } = self;
+
+        let input = |state, placeholder: &str, value: &str, field: LoginField| {
+            TextInput::new(state, placeholder, value, move |value| {
+                Message::Login(pane, LoginMessage::FieldChanged(field, value))
+            })
+            .style(theme)
+            .padding(5)
+            .on_submit(Message::LoginSubmitted(pane))
+        };
+
+        let mut content = Column::new().padding(10).spacing(15);
+        if *unlocking {
+            content = content
+                .push(Text::new("Unlock your login").size(28))
+                .push(Text::new(
+                    "Enter the passphrase you saved your login with last time.",
+                ))
+                .push(labelled(
+                    "Passphrase",
+                    input(passphrase_input, "", passphrase, LoginField::Passphrase).password(),
+                ));
+        } else {
+            content = content
+                .push(Text::new("Log in").size(28))
+                .push(labelled(
+                    "Email",
+                    input(email_input, "", email, LoginField::Email),
+                ))
+                .push(labelled(
+                    "Password",
+                    input(password_input, "", password, LoginField::Password).password(),
+                ))
+                .push(Text::new(
+                    "To stay logged in, choose a passphrase to lock the saved login with. \
+                     Your password is never saved.",
+                ))
+                .push(labelled(
+                    "Passphrase",
+                    input(
+                        passphrase_input,
+                        "Leave empty to log in for this session only",
+                        passphrase,
+                        LoginField::Passphrase,
+                    )
+                    .password(),
+                ));
+        }
+
+        let label = match (*busy, *unlocking) {
+            (true, _) => "Logging in…",
+            (false, true) => "Unlock",
+            (false, false) => "Log in",
+        };
+        let mut submit_button = Button::new(submit, Text::new(label)).style(theme);
+        if !*busy {
+            submit_button = submit_button.on_press(Message::LoginSubmitted(pane));
+        }
+        let mut buttons = Row::new().spacing(10).push(submit_button);
+        if *unlocking {
+            buttons = buttons.push(
+                Button::new(use_password, Text::new("Use password instead"))
+                    .style(theme)
+                    .on_press(Message::Login(pane, LoginMessage::UsePasswordClicked)),
+            );
+        }
+        buttons = buttons.push(
+            Button::new(cancel, Text::new("Cancel"))
+                .style(theme)
+                .on_press(Message::Close(pane)),
+        );
+
+        content.push(buttons).into()
+    }
+}
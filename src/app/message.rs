@@ -1,13 +1,100 @@
+use crate::app::category::Category;
+use crate::app::category_manager::CategoryMessage;
+use crate::app::command_palette::{PaletteEntry, PaletteMessage};
+use crate::app::error::Error;
+use crate::app::login_form::LoginMessage;
+use crate::app::photo_cache::Photo;
+use crate::app::profile_form::ProfileMessage;
+use crate::app::recipe_editor::EditorMessage;
+use crate::app::recipe_filter::FilterMessage;
+use crate::app::recipe_merge::MergeMessage;
+use crate::app::recipe_sort::SortKey;
+use crate::app::recipe_sync::SyncEvent;
+use crate::app::settings_form::SettingsMessage;
+use crate::app::trash::TrashMessage;
+use iced::keyboard::{KeyCode, Modifiers};
 use iced::pane_grid::{self};
 
 #[derive(Debug, Clone)]
-#[allow(unused)]
+#[allow(unused, clippy::large_enum_variant)]
 pub enum Message {
     Split(pane_grid::Axis, pane_grid::Pane),
     Close(pane_grid::Pane),
+    Clicked(pane_grid::Pane),
+    Dragged(pane_grid::DragEvent),
+    Resized(pane_grid::ResizeEvent),
+    /// The window is being closed
+    CloseRequested,
+    /// The window's new width and height
+    WindowResized(u32, u32),
+    KeyPressed(KeyCode, Modifiers),
+    CommandPalette(PaletteMessage),
+    PaletteEntryPicked(PaletteEntry),
     NewRecipeClicked,
     RecipeFetched(Option<paprika_api::api::Recipe>),
+    Sync(SyncEvent),
+    SyncNowClicked,
+    /// A `photo_cache::photo_key` and the downloaded photo
+    PhotoFetched(String, Result<Photo, Error>),
     RecipeClicked(String),
+    EditRecipeClicked(String),
+    /// A recipe uid; the pane hears back if saving fails
+    TrashRecipeClicked(pane_grid::Pane, String),
+    RestoreRecipeClicked(pane_grid::Pane, String),
+    TrashClicked,
+    ShoppingListClicked,
+    /// Adds a recipe uid to the shopping list, or takes it off
+    ShoppingListToggled(pane_grid::Pane, String),
+    Trash(pane_grid::Pane, TrashMessage),
+    TrashDeleteConfirmed(pane_grid::Pane),
+    OpenUrl(String),
+    /// The recipe as stored locally, and why it couldn't be uploaded if it wasn't
+    RecipeSaved(
+        pane_grid::Pane,
+        Result<(paprika_api::api::Recipe, Option<Error>), Error>,
+    ),
+    /// Opens the merge view for a recipe uid
+    ResolveConflictClicked(String),
+    Merge(pane_grid::Pane, MergeMessage),
+    MergeSaved(pane_grid::Pane),
+    Editor(pane_grid::Pane, EditorMessage),
+    EditorSaved(pane_grid::Pane),
+    EditorCancelled(pane_grid::Pane),
+    /// Closes an editor, throwing its changes away
+    EditorDiscarded(pane_grid::Pane),
+    Filter(pane_grid::Pane, FilterMessage),
+    CategoriesFetched(Result<Vec<Category>, Error>),
+    /// A category uid, or `None` for every recipe
+    CategorySelected(Option<String>),
+    ManageCategoriesClicked,
+    CategoryManager(pane_grid::Pane, CategoryMessage),
+    CategoriesSaveClicked(pane_grid::Pane),
+    /// The saved categories, and recipes that were changed to drop deleted categories
+    CategoriesSaved(
+        pane_grid::Pane,
+        Result<(Vec<Category>, Vec<paprika_api::api::Recipe>), Error>,
+    ),
+    SortKeyChanged(SortKey),
+    SettingsClicked,
+    SettingsForm(pane_grid::Pane, SettingsMessage),
+    SettingsSaved(pane_grid::Pane),
+    SortDirectionToggled,
+    ListModeToggled,
+    /// A profile id
+    ProfileSelected(String),
+    NewProfileClicked,
+    ProfileForm(pane_grid::Pane, ProfileMessage),
+    ProfileCreated(pane_grid::Pane),
     LoginClicked,
-    AccountChanged(String, String)
+    Login(pane_grid::Pane, LoginMessage),
+    LoginSubmitted(pane_grid::Pane),
+    LoggedIn(pane_grid::Pane, Result<(), Error>),
+    LogoutClicked,
+    /// Go ahead with what was waiting on losing changes that haven't been uploaded
+    DiscardConfirmed,
+    DiscardCancelled,
+    /// The token is gone; the result says whether the saved login could be deleted too
+    LoggedOut(Result<(), Error>),
+    ErrorOccurred(Error),
+    ErrorDismissed,
 }
@@ -1,29 +1,167 @@
+use crate::app::category::{self, Category};
+use crate::app::profile::Profile;
+use crate::app::recipe_sync::SyncProgress;
+use crate::app::theme::Theme;
 use crate::app::Message;
 use crate::app::SimpleButton;
 
-use iced::{Column, Element};
+use iced::{
+    button, pick_list, scrollable, Button, Column, Element, Length, PickList, Scrollable, Text,
+};
+use std::time::SystemTime;
+
+const INDENT: &str = "    ";
 
 pub struct NavPane {
     pub new: SimpleButton,
     pub login: SimpleButton,
+    pub logout: SimpleButton,
+    pub manage_categories: SimpleButton,
+    pub trash: button::State,
+    pub shopping_list: SimpleButton,
+    pub new_profile: SimpleButton,
+    pub settings: SimpleButton,
+    profile_picker: pick_list::State<Profile>,
+    sync_now: button::State,
+    scroll: scrollable::State,
+    all_recipes: button::State,
+    category_buttons: Vec<button::State>,
 }
 
 impl NavPane {
     pub fn new() -> Self {
-        let login = SimpleButton::new("Login".into(), Message::LoginClicked);
+        let login = SimpleButton::new("Log in".into(), Message::LoginClicked);
+        let logout = SimpleButton::new("Log out".into(), Message::LogoutClicked);
         let new_simple = SimpleButton::new("New recipe".into(), Message::NewRecipeClicked);
+        let manage_categories =
+            SimpleButton::new("Manage categories".into(), Message::ManageCategoriesClicked);
 
         Self {
             new: new_simple,
-            login: login,
+            login,
+            logout,
+            manage_categories,
+            trash: button::State::new(),
+            shopping_list: SimpleButton::new("Shopping list".into(), Message::ShoppingListClicked),
+            new_profile: SimpleButton::new("New profile".into(), Message::NewProfileClicked),
+            settings: SimpleButton::new("Settings".into(), Message::SettingsClicked),
+            profile_picker: pick_list::State::default(),
+            sync_now: button::State::new(),
+            scroll: scrollable::State::new(),
+            all_recipes: button::State::new(),
+            category_buttons: Vec::new(),
         }
     }
 
-    pub fn view(&mut self) -> Element<Message> {
+    /// `selected` is the uid of the category the recipe list is showing, if any, `unsynced`
+    /// how many local changes are waiting to be uploaded, `trashed` how many recipes are in the
+    /// trash, and `profile` the one of `profiles` in use
+    #[allow(clippy::too_many_arguments)]
+    pub fn view(
+        &mut self,
+        categories: &[Category],
+        selected: Option<&str>,
+        sync: &SyncProgress,
+        unsynced: usize,
+        trashed: usize,
+        logged_in: bool,
+        profiles: &[Profile],
+        profile: &Profile,
+        theme: Theme,
+    ) -> Element<'_, Message> {
         let mut column = Column::new();
 
-        column = column.push(self.login.to_button());
-        column = column.push(self.new.to_button());
+        column = column.push(
+            PickList::new(
+                &mut self.profile_picker,
+                profiles.to_vec(),
+                Some(profile.clone()),
+                |profile| Message::ProfileSelected(profile.id),
+            )
+            .style(theme),
+        );
+        column = column.push(self.new_profile.to_button(theme));
+
+        let status = match logged_in || sync.running {
+            true => sync.to_string(),
+            false => "Not logged in".into(),
+        };
+        column = column.push(Text::new(status).size(16));
+        column = column.push(Text::new(sync.last_synced_text(SystemTime::now())).size(16));
+        match unsynced {
+            0 => (),
+            1 => column = column.push(Text::new("1 change waiting to upload").size(16)),
+            n => {
+                column = column.push(Text::new(format!("{} changes waiting to upload", n)).size(16))
+            }
+        }
+
+        let mut sync_now = Button::new(&mut self.sync_now, Text::new("Sync now")).style(theme);
+        if !sync.running {
+            sync_now = sync_now.on_press(Message::SyncNowClicked);
+        }
+        column = column.push(sync_now);
+
+        column = column.push(match logged_in {
+            true => self.logout.to_button(theme),
+            false => self.login.to_button(theme),
+        });
+        column = column.push(self.new.to_button(theme));
+        column = column.push(self.shopping_list.to_button(theme));
+
+        let marker = |is_selected: bool| if is_selected { "• " } else { "" };
+
+        let mut tree = Scrollable::new(&mut self.scroll)
+            .style(theme)
+            .width(Length::Fill)
+            .spacing(2)
+            .push(Text::new("Categories").size(20))
+            .push(
+                Button::new(
+                    &mut self.all_recipes,
+                    Text::new(format!("{}All recipes", marker(selected.is_none()))),
+                )
+                .style(theme)
+                .on_press(Message::CategorySelected(None)),
+            );
+
+        self.category_buttons
+            .resize_with(categories.len(), button::State::new);
+        let mut buttons: Vec<Option<&mut button::State>> =
+            self.category_buttons.iter_mut().map(Some).collect();
+        for (depth, i) in category::tree(categories) {
+            let state = match buttons[i].take() {
+                Some(state) => state,
+                None => continue,
+            };
+            let category = &categories[i];
+            let label = format!(
+                "{}{}{}",
+                INDENT.repeat(depth),
+                marker(selected == Some(category.uid.as_str())),
+                category.name
+            );
+
+            tree = tree.push(
+                Button::new(state, Text::new(label))
+                    .style(theme)
+                    .on_press(Message::CategorySelected(Some(category.uid.clone()))),
+            );
+        }
+
+        column = column.push(tree);
+        column = column.push(self.manage_categories.to_button(theme));
+        let trash = match trashed {
+            0 => "Trash".to_string(),
+            n => format!("Trash ({})", n),
+        };
+        column = column.push(
+            Button::new(&mut self.trash, Text::new(trash))
+                .style(theme)
+                .on_press(Message::TrashClicked),
+        );
+
+        column = column.push(self.settings.to_button(theme));
 
         column.into()
     }
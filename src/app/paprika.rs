@@ -1,123 +1,236 @@
 use crate::app::account::Account;
+use crate::app::backend::RecipeBackend;
+use crate::app::category::Category;
+use crate::app::error::{Error, Result};
 use paprika_api::api;
 use std::env;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// The client as shared between the UI and background tasks. The lock is async, so a task
+/// waiting for it yields instead of tying up a thread.
+pub type SharedPaprika = Arc<Mutex<Paprika>>;
+
+/// A logged-in handle for downloading recipes without holding the client's lock, so several
+/// downloads can be in flight at once
+#[derive(Clone)]
+pub struct Session {
+    backend: Arc<dyn RecipeBackend>,
+    token: String,
+}
+
+impl Session {
+    pub async fn recipe(&self, uid: &str) -> Result<api::Recipe> {
+        self.backend.recipe(&self.token, uid).await
+    }
+}
 
 pub struct Paprika {
     pub token: String,
     pub recipe_entries: Vec<api::RecipeEntry>,
-    pub last_fetched: usize,
-    pub account: Account,
-    account_hash: String,
+    /// Whether `PAPRIKA_EMAIL` and `PAPRIKA_PASSWORD` may be used to log in. Only the default
+    /// profile does, so other profiles never end up on that account.
+    pub env_login: bool,
+    backend: Arc<dyn RecipeBackend>,
 }
 
 impl Paprika {
-    pub fn new() -> Self {
+    pub fn with_backend(backend: Box<dyn RecipeBackend>) -> Self {
         Self {
             token: "".into(),
             recipe_entries: Vec::new(),
-            last_fetched: 0,
-            account: Account::new("".into(), "".into()),
-            account_hash: "".into(),
+            env_login: false,
+            backend: backend.into(),
         }
     }
 
-    pub async fn login(&mut self) -> Result<String, Box<dyn std::error::Error>> {
-        if let Ok(email) = env::var("PAPRIKA_EMAIL") {
-            if let Ok(password) = env::var("PAPRIKA_PASSWORD") {
-                let res = paprika_api::api::login(&email, &password).await;
-                match res {
-                    Ok(t) => {
-                        println!("Yay! Token: {}", t.token);
-                        self.token = t.token.into();
-                        Ok(self.token.to_owned())
-                    }
-                    Err(e) => Err(e.into()),
-                }
-            } else {
-                Err("No password found; is the PAPRIKA_PASSWORD environment variable set?".into())
-            }
-        } else {
-            Err("No email found; is the PAPRIKA_EMAIL environment variable set?".into())
-        }
+    /// Trades `account` for a token, replacing any earlier login
+    pub async fn log_in(&mut self, account: &Account) -> Result<()> {
+        self.token = self.backend.login(account).await?;
+        self.recipe_entries.clear();
+        Ok(())
     }
 
-    pub async fn account_login(&mut self) -> Result<String, Box<dyn std::error::Error>> {
-        let res = paprika_api::api::login(&self.account.username, &self.account.password).await;
-        match res {
-            Ok(t) => {
-                println!("Yay! Token: {}", t.token);
-                self.token = t.token.into();
-                Ok(self.token.to_owned())
-            }
-            Err(e) => Err(e.into()),
-        }
+    pub fn log_out(&mut self) {
+        self.token.clear();
+        self.recipe_entries.clear();
     }
 
-    pub async fn fetch_recipe_list(&mut self) {
-        if self.account.get_hash() != self.account_hash
-            && !self.account.username.is_empty()
-            && !self.account.password.is_empty()
-        {
-            self.account_hash = self.account.get_hash();
+    /// Logs in if we have no token yet. Backends without accounts log in by themselves, and
+    /// `PAPRIKA_EMAIL` and `PAPRIKA_PASSWORD` are still honoured for scripted runs when
+    /// `env_login` is set; everyone else logs in through the login dialog.
+    async fn ensure_logged_in(&mut self) -> Result<()> {
+        if !self.token.is_empty() {
+            return Ok(());
+        }
+        if !self.backend.requires_credentials() {
+            return self.log_in(&Account::new("".into(), "".into())).await;
+        }
 
-            let login_ret = self.account_login().await;
-            match login_ret {
-                Ok(_) => println!("Logged in with account!"),
-                Err(_) => println!("Couldn't log in with account"),
-            }
-        } else if self.token.is_empty() {
-            let login_ret = self.login().await;
-            match login_ret {
-                Ok(_) => println!("Logged in!"),
-                Err(_) => println!("Couldn't log in!"),
+        match (env::var("PAPRIKA_EMAIL"), env::var("PAPRIKA_PASSWORD")) {
+            (Ok(email), Ok(password)) if self.env_login => {
+                self.log_in(&Account::new(email, password)).await
             }
+            _ => Err(Error::LoggedOut),
         }
+    }
 
-        if !self.token.is_empty() {
-            self.recipe_entries = paprika_api::api::get_recipes(&self.token).await.unwrap();
-        }
+    pub async fn fetch_recipe_list(&mut self) -> Result<()> {
+        self.ensure_logged_in().await?;
+
+        self.recipe_entries = self.backend.recipe_entries(&self.token).await?;
+        Ok(())
     }
 
-    pub async fn get_recipe_by_id(&mut self, id: &str) -> paprika_api::api::Recipe {
-        if self.token.is_empty() {
-            self.login().await.expect("Couldn't log in");
-        }
-        if self.recipe_entries.len() == 0 {
-            self.fetch_recipe_list().await;
+    /// The current login, for use once `fetch_recipe_list` has made sure there is one
+    pub fn session(&self) -> Session {
+        Session {
+            backend: self.backend.clone(),
+            token: self.token.clone(),
         }
+    }
 
-        if !self.token.is_empty() {
-            let recipe = paprika_api::api::get_recipe_by_id(&self.token, id)
-                .await
-                .unwrap();
-            return recipe;
+    pub async fn fetch_categories(&mut self) -> Result<Vec<Category>> {
+        self.ensure_logged_in().await?;
+
+        self.backend.categories(&self.token).await
+    }
+
+    pub async fn update_categories(&mut self, categories: &[Category]) -> Result<()> {
+        self.ensure_logged_in().await?;
+
+        self.backend
+            .upload_categories(&self.token, categories)
+            .await
+    }
+
+    pub async fn get_recipe_by_id(&mut self, id: &str) -> Result<api::Recipe> {
+        self.ensure_logged_in().await?;
+        if self.recipe_entries.is_empty() {
+            self.fetch_recipe_list().await?;
         }
-        return paprika_api::api::Recipe::default();
+
+        self.backend.recipe(&self.token, id).await
     }
 
     #[allow(unused)]
-    pub async fn fetch_recipe_by_id(&mut self, id: &str) {
-        if self.token.is_empty() {
-            self.login().await.expect("Couldn't log in");
-        }
-        if self.recipe_entries.len() == 0 {
-            self.fetch_recipe_list().await;
+    pub async fn fetch_recipe_by_id(&mut self, id: &str) -> Result<()> {
+        self.ensure_logged_in().await?;
+        if self.recipe_entries.is_empty() {
+            self.fetch_recipe_list().await?;
         }
+        Ok(())
     }
 
-    pub async fn update_recipe(&mut self, recipe: &mut paprika_api::api::Recipe) {
-        if self.token.is_empty() {
-            self.login().await.expect("Couldn't log in");
-        }
+    pub async fn update_recipe(&mut self, recipe: &mut api::Recipe) -> Result<()> {
+        self.ensure_logged_in().await?;
 
         recipe.hash.clear();
 
-        let success = paprika_api::api::upload_recipe(&self.token, recipe)
-            .await
-            .unwrap();
+        self.backend.upload_recipe(&self.token, recipe).await
+    }
+
+    pub async fn delete_recipe(&mut self, uid: &str) -> Result<()> {
+        self.ensure_logged_in().await?;
+
+        self.backend.delete_recipe(&self.token, uid).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::backend::mock_server::{self, MockServer};
+    use crate::app::backend::PaprikaCloud;
+
+    fn logged_out(server: &MockServer) -> Paprika {
+        Paprika::with_backend(Box::new(PaprikaCloud::with_base_url(&server.base_url())))
+    }
+
+    #[tokio::test]
+    async fn log_in_stores_token() {
+        let server = MockServer::start().await;
+        let paprika = mock_server::logged_in(&server).await;
+
+        assert!(!paprika.token.is_empty());
+    }
+
+    #[tokio::test]
+    async fn log_in_rejects_bad_password() {
+        let server = MockServer::start().await;
+        let mut paprika = logged_out(&server);
+
+        let account = Account::new(mock_server::EMAIL.into(), "wrong".into());
+        assert!(matches!(
+            paprika.log_in(&account).await,
+            Err(Error::Auth(_))
+        ));
+        assert!(paprika.token.is_empty());
+    }
+
+    #[tokio::test]
+    async fn nothing_is_fetched_once_logged_out() {
+        let server = MockServer::start().await;
+        let mut paprika = mock_server::logged_in(&server).await;
+        paprika.fetch_recipe_list().await.unwrap();
+
+        paprika.log_out();
+        assert!(paprika.recipe_entries.is_empty());
+        assert_eq!(paprika.fetch_recipe_list().await, Err(Error::LoggedOut));
+    }
 
-        if !success {
-            println!("Failed to update recipe");
+    #[tokio::test]
+    async fn full_sync_fetches_every_recipe() {
+        let server = MockServer::start().await;
+        let mut paprika = mock_server::logged_in(&server).await;
+
+        paprika.fetch_recipe_list().await.unwrap();
+        assert_eq!(paprika.recipe_entries.len(), server.recipe_count());
+
+        let entries: Vec<(String, String)> = paprika
+            .recipe_entries
+            .iter()
+            .map(|entry| (entry.uid.clone(), entry.hash.clone()))
+            .collect();
+        for (uid, hash) in entries {
+            let recipe = paprika.get_recipe_by_id(&uid).await.unwrap();
+            assert_eq!(recipe.uid, uid);
+            assert_eq!(recipe.hash, hash);
         }
+        assert_eq!(server.request_count("/sync/recipes/"), 1);
+    }
+
+    #[tokio::test]
+    async fn update_recipe_uploads_new_recipe() {
+        let server = MockServer::start().await;
+        let mut paprika = mock_server::logged_in(&server).await;
+        paprika.fetch_recipe_list().await.unwrap();
+
+        let mut recipe = api::Recipe {
+            name: "Shakshuka".into(),
+            ingredients: "6 eggs\n1 can tomatoes".into(),
+            ..Default::default()
+        };
+        paprika.update_recipe(&mut recipe).await.unwrap();
+
+        assert!(!recipe.uid.is_empty());
+        let uploaded = server.recipe(&recipe.uid).expect("Recipe wasn't uploaded");
+        assert_eq!(uploaded.name, "Shakshuka");
+        assert_eq!(uploaded.hash, recipe.hash);
+    }
+
+    #[tokio::test]
+    async fn categories_round_trip() {
+        let server = MockServer::start().await;
+        let mut paprika = mock_server::logged_in(&server).await;
+
+        let mut categories = paprika.fetch_categories().await.unwrap();
+        assert_eq!(categories, server.categories());
+
+        categories[0].name = "Mains".into();
+        categories.push(Category::new("Breakfast".into(), None, 2));
+        paprika.update_categories(&categories).await.unwrap();
+
+        assert_eq!(server.categories(), categories);
     }
 }
@@ -0,0 +1,221 @@
+//! Where Ancho keeps its files.
+//!
+//! Each directory follows the XDG base-directory spec (or the platform's equivalent) unless it's
+//! overridden. The cache can be moved from `settings.toml` as well as from the environment:
+//!
+//! | Directory | Environment override | Default on Linux       |
+//! |-----------|----------------------|------------------------|
+//! | config    | `ANCHO_CONFIG_DIR`   | `~/.config/ancho`      |
+//! | cache     | `ANCHO_CACHE_DIR`    | `~/.cache/ancho`       |
+//! | data      | `ANCHO_DATA_DIR`     | `~/.local/share/ancho` |
+
+use crate::app::error::{Error, Result};
+
+use directories::ProjectDirs;
+use std::{env, fs, path};
+
+const SETTINGS_FILE: &str = "settings.toml";
+const PROFILES_DIR: &str = "profiles";
+
+/// Where older versions kept their cache, relative to the working directory
+const LEGACY_CACHE_DIR: &str = "recipe_cache";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Paths {
+    pub config_dir: path::PathBuf,
+    pub cache_dir: path::PathBuf,
+    pub data_dir: path::PathBuf,
+}
+
+fn project_dirs() -> Result<ProjectDirs> {
+    ProjectDirs::from("", "", "Ancho")
+        .ok_or_else(|| Error::CacheIo("couldn't find the home directory".into()))
+}
+
+fn from_env(name: &str) -> Option<path::PathBuf> {
+    env::var_os(name)
+        .filter(|value| !value.is_empty())
+        .map(path::PathBuf::from)
+}
+
+/// The directory holding `settings.toml`
+pub fn config_dir() -> Result<path::PathBuf> {
+    match from_env("ANCHO_CONFIG_DIR") {
+        Some(dir) => Ok(dir),
+        None => Ok(project_dirs()?.config_dir().to_path_buf()),
+    }
+}
+
+pub fn settings_file(config_dir: &path::Path) -> path::PathBuf {
+    config_dir.join(SETTINGS_FILE)
+}
+
+impl Paths {
+    /// Works out every directory; `cache_override` is the cache directory from the settings file
+    pub fn resolve(config_dir: path::PathBuf, cache_override: Option<&path::Path>) -> Result<Self> {
+        let cache_dir = match (from_env("ANCHO_CACHE_DIR"), cache_override) {
+            (Some(dir), _) => dir,
+            (None, Some(dir)) => dir.to_path_buf(),
+            (None, None) => project_dirs()?.cache_dir().to_path_buf(),
+        };
+        let data_dir = match from_env("ANCHO_DATA_DIR") {
+            Some(dir) => dir,
+            None => project_dirs()?.data_dir().to_path_buf(),
+        };
+
+        Ok(Self {
+            config_dir,
+            cache_dir,
+            data_dir,
+        })
+    }
+
+    /// The paths older versions used, for when no better location can be found
+    pub fn legacy() -> Self {
+        Self {
+            config_dir: path::PathBuf::from("."),
+            cache_dir: path::PathBuf::from(LEGACY_CACHE_DIR),
+            data_dir: path::PathBuf::from("."),
+        }
+    }
+
+    pub fn settings_file(&self) -> path::PathBuf {
+        settings_file(&self.config_dir)
+    }
+
+    /// Where one profile keeps its database, login and cache; settings stay shared
+    pub fn for_profile(&self, id: &str) -> Self {
+        Self {
+            config_dir: self.config_dir.clone(),
+            cache_dir: self.cache_dir.join(PROFILES_DIR).join(id),
+            data_dir: self.data_dir.join(PROFILES_DIR).join(id),
+        }
+    }
+}
+
+/// Moves a `recipe_cache` directory left in the working directory by an older version into
+/// `cache_dir`, unless the cache has already been started there
+pub fn migrate_legacy_cache(cache_dir: &path::Path) -> Result<()> {
+    migrate_dir(path::Path::new(LEGACY_CACHE_DIR), cache_dir)
+}
+
+/// Moves the cache when the settings change where it goes, unless the new place is in use
+pub fn move_cache(from: &path::Path, to: &path::Path) -> Result<()> {
+    if from != to && (to.starts_with(from) || from.starts_with(to)) {
+        return Err(Error::CacheIo(format!(
+            "can't move the cache from {} to {}, one is inside the other",
+            from.display(),
+            to.display()
+        )));
+    }
+    migrate_dir(from, to)
+}
+
+/// Moves a file or directory to `to`, unless something is there already
+pub fn move_path(from: &path::Path, to: &path::Path) -> Result<()> {
+    if !from.exists() || to.exists() {
+        return Ok(());
+    }
+    if let Some(dir) = to.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    if from.is_dir() {
+        migrate_dir(from, to)
+    } else {
+        move_file(from, to)
+    }
+}
+
+fn migrate_dir(legacy: &path::Path, target: &path::Path) -> Result<()> {
+    if !legacy.is_dir() || same_file(legacy, target) {
+        return Ok(());
+    }
+    // a cache that's already in use wins over the old one
+    if target.is_dir() && fs::read_dir(target)?.next().is_some() {
+        return Ok(());
+    }
+
+    fs::create_dir_all(target)?;
+    for entry in fs::read_dir(legacy)? {
+        let entry = entry?;
+        let to = target.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            migrate_dir(&entry.path(), &to)?;
+        } else {
+            move_file(&entry.path(), &to)?;
+        }
+    }
+    fs::remove_dir(legacy)?;
+
+    Ok(())
+}
+
+/// Renames `from` to `to`, copying instead when they're on different filesystems
+fn move_file(from: &path::Path, to: &path::Path) -> Result<()> {
+    if fs::rename(from, to).is_err() {
+        fs::copy(from, to)?;
+        fs::remove_file(from)?;
+    }
+    Ok(())
+}
+
+fn same_file(a: &path::Path, b: &path::Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_cache_moves_into_place() {
+        let dir = tempfile::tempdir().unwrap();
+        let legacy = dir.path().join("recipe_cache");
+        fs::create_dir_all(legacy.join("photos")).unwrap();
+        fs::write(legacy.join("0C1A4B3E"), "{}").unwrap();
+        fs::write(legacy.join("photos").join("abc"), "jpeg").unwrap();
+
+        let target = dir.path().join("xdg").join("ancho");
+        migrate_dir(&legacy, &target).unwrap();
+
+        assert!(!legacy.exists());
+        assert_eq!(fs::read_to_string(target.join("0C1A4B3E")).unwrap(), "{}");
+        assert_eq!(
+            fs::read_to_string(target.join("photos").join("abc")).unwrap(),
+            "jpeg"
+        );
+    }
+
+    #[test]
+    fn cache_does_not_move_into_itself() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = dir.path().join("cache");
+        fs::create_dir_all(&cache).unwrap();
+        fs::write(cache.join("categories.json"), "[]").unwrap();
+
+        assert!(move_cache(&cache, &cache.join("inner")).is_err());
+        assert!(move_cache(&cache, dir.path()).is_err());
+        move_cache(&cache, &dir.path().join("moved")).unwrap();
+        assert!(dir.path().join("moved").join("categories.json").is_file());
+    }
+
+    #[test]
+    fn existing_cache_is_left_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let legacy = dir.path().join("recipe_cache");
+        let target = dir.path().join("cache");
+        fs::create_dir_all(&legacy).unwrap();
+        fs::create_dir_all(&target).unwrap();
+        fs::write(legacy.join("old"), "{}").unwrap();
+        fs::write(target.join("new"), "{}").unwrap();
+
+        migrate_dir(&legacy, &target).unwrap();
+
+        assert!(legacy.join("old").exists());
+        assert!(!target.join("old").exists());
+    }
+}
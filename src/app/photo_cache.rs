@@ -0,0 +1,247 @@
+use crate::app::error::{Error, Result};
+
+use iced::svg;
+use image::imageops::FilterType;
+use paprika_api::api::Recipe;
+use sha2::{Digest, Sha256};
+use std::{fs, path};
+
+const PHOTO_DIR: &str = "photos";
+const THUMBNAIL_SIZE: u32 = 96;
+/// Twice a grid card's width, so cards stay sharp on high-DPI screens
+const CARD_SIZE: u32 = 360;
+const PLACEHOLDER_IMAGE: &[u8] = include_bytes!("../../resources/recipe.svg");
+
+/// A recipe photo on disk, along with a small copy for lists and a medium one for grid cards
+#[derive(Debug, Clone, PartialEq)]
+pub struct Photo {
+    pub full: path::PathBuf,
+    pub thumbnail: path::PathBuf,
+    pub card: path::PathBuf,
+}
+
+impl Photo {
+    /// How much space the photo and its smaller copies take, in bytes
+    pub fn size(&self) -> u64 {
+        [&self.full, &self.thumbnail, &self.card]
+            .iter()
+            .map(|file| fs::metadata(file).map_or(0, |meta| meta.len()))
+            .sum()
+    }
+}
+
+/// Stands in for recipes without a photo. It's built in, so it shows wherever Ancho is started
+/// from.
+pub fn placeholder() -> svg::Handle {
+    svg::Handle::from_memory(PLACEHOLDER_IMAGE)
+}
+
+/// Photos are kept in their own directory inside the recipe cache
+pub fn photo_dir(cache_dir: &path::Path) -> path::PathBuf {
+    cache_dir.join(PHOTO_DIR)
+}
+
+/// Deletes every downloaded photo
+pub fn clear(photo_dir: &path::Path) -> Result<()> {
+    if photo_dir.is_dir() {
+        fs::remove_dir_all(photo_dir)?;
+    }
+    Ok(())
+}
+
+/// Where to download the recipe's photo from; Paprika's own copy wins over the original source
+pub fn photo_url(recipe: &Recipe) -> Option<&str> {
+    recipe
+        .photo_url
+        .as_deref()
+        .or(recipe.image_url.as_deref())
+        .filter(|url| !url.is_empty())
+}
+
+/// Identifies the recipe's photo on disk: Paprika's `photo_hash`, or a hash of the URL for
+/// recipes that only have an `image_url`. The key names a file, so a `photo_hash` that isn't
+/// plain hex is ignored rather than trusted as a path.
+pub fn photo_key(recipe: &Recipe) -> Option<String> {
+    match recipe
+        .photo_hash
+        .as_deref()
+        .filter(|hash| !hash.is_empty() && hash.chars().all(|c| c.is_ascii_hexdigit()))
+    {
+        Some(hash) => Some(hash.to_lowercase()),
+        None => photo_url(recipe).map(|url| hex::encode(Sha256::digest(url.as_bytes()))),
+    }
+}
+
+fn paths(photo_dir: &path::Path, key: &str) -> Photo {
+    Photo {
+        full: photo_dir.join(key),
+        thumbnail: photo_dir.join(format!("{}_thumb.png", key)),
+        card: photo_dir.join(format!("{}_card.jpg", key)),
+    }
+}
+
+/// The photo stored under `key`, if it has been downloaded before
+pub fn cached(photo_dir: &path::Path, key: &str) -> Option<Photo> {
+    let photo = paths(photo_dir, key);
+
+    if photo.full.is_file() && photo.thumbnail.is_file() && photo.card.is_file() {
+        Some(photo)
+    } else {
+        None
+    }
+}
+
+/// Saves a downloaded photo under `key` and makes its smaller copies
+pub fn store(photo_dir: &path::Path, key: &str, bytes: &[u8]) -> Result<Photo> {
+    let decoded = image::load_from_memory(bytes)
+        .map_err(|e| Error::Parse(format!("photo {} isn't an image: {}", key, e)))?;
+
+    if !photo_dir.is_dir() {
+        fs::create_dir_all(photo_dir)?;
+    }
+
+    let photo = paths(photo_dir, key);
+    fs::write(&photo.full, bytes)?;
+    decoded
+        .resize(THUMBNAIL_SIZE, THUMBNAIL_SIZE, FilterType::Triangle)
+        .save_with_format(&photo.thumbnail, image::ImageFormat::Png)
+        .map_err(|e| Error::CacheIo(format!("thumbnail for photo {}: {}", key, e)))?;
+    decoded
+        .resize(CARD_SIZE, CARD_SIZE, FilterType::Triangle)
+        .to_rgb8()
+        .save_with_format(&photo.card, image::ImageFormat::Jpeg)
+        .map_err(|e| Error::CacheIo(format!("card for photo {}: {}", key, e)))?;
+
+    Ok(photo)
+}
+
+/// Deletes the photos downloaded longest ago until the rest, smaller copies included, fit in
+/// `max_bytes`. Returns how much space the rest take.
+pub fn prune(photo_dir: &path::Path, max_bytes: u64) -> Result<u64> {
+    if !photo_dir.is_dir() {
+        return Ok(0);
+    }
+
+    let mut photos = Vec::new();
+    let mut total = 0;
+    for entry in fs::read_dir(photo_dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.ends_with("_thumb.png") || name.ends_with("_card.jpg") {
+            continue;
+        }
+        let photo = paths(photo_dir, &name);
+        let size = photo.size();
+        total += size;
+        photos.push((entry.metadata()?.modified()?, size, photo));
+    }
+
+    photos.sort_by_key(|(modified, _, _)| *modified);
+    for (_, size, photo) in photos {
+        if total <= max_bytes {
+            break;
+        }
+        fs::remove_file(&photo.full)?;
+        for copy in [&photo.thumbnail, &photo.card] {
+            if copy.is_file() {
+                fs::remove_file(copy)?;
+            }
+        }
+        total -= size;
+    }
+    Ok(total)
+}
+
+/// Downloads the photo at `url` into the cache under `key`. Decoding and resizing happen on a
+/// blocking thread, away from the tasks keeping the app responsive.
+pub async fn download(photo_dir: &path::Path, key: &str, url: &str) -> Result<Photo> {
+    let response = reqwest::get(url).await?.error_for_status()?;
+    let bytes = response.bytes().await?;
+
+    let (photo_dir, key) = (photo_dir.to_path_buf(), key.to_string());
+    tokio::task::spawn_blocking(move || store(&photo_dir, &key, &bytes))
+        .await
+        .map_err(|e| Error::CacheIo(format!("storing a photo: {}", e)))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::GenericImageView;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        image::DynamicImage::new_rgb8(width, height)
+            .write_to(&mut bytes, image::ImageOutputFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn stored_photos_get_smaller_copies() {
+        let dir = tempfile::tempdir().unwrap();
+        let photo_dir = photo_dir(dir.path());
+        assert!(cached(&photo_dir, "abc123").is_none());
+
+        let photo = store(&photo_dir, "abc123", &png(640, 480)).unwrap();
+
+        let thumbnail = image::open(&photo.thumbnail).unwrap();
+        assert_eq!(thumbnail.width(), THUMBNAIL_SIZE);
+        assert!(thumbnail.height() <= THUMBNAIL_SIZE);
+        let card = image::open(&photo.card).unwrap();
+        assert_eq!(card.width(), CARD_SIZE);
+        assert!(card.height() <= CARD_SIZE);
+        assert_eq!(cached(&photo_dir, "abc123"), Some(photo));
+    }
+
+    #[test]
+    fn pruning_deletes_the_oldest_photos() {
+        let dir = tempfile::tempdir().unwrap();
+        let photo_dir = photo_dir(dir.path());
+        assert_eq!(prune(&photo_dir, 0).unwrap(), 0);
+
+        let size = store(&photo_dir, "old", &png(64, 64)).unwrap().size();
+        // modification times can be coarse, so the newer photo has to come a little later
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        store(&photo_dir, "new", &png(64, 64)).unwrap();
+
+        assert_eq!(prune(&photo_dir, 3 * size).unwrap(), 2 * size);
+        assert_eq!(prune(&photo_dir, size + size / 2).unwrap(), size);
+        assert!(cached(&photo_dir, "old").is_none());
+        assert!(cached(&photo_dir, "new").is_some());
+    }
+
+    #[test]
+    fn photos_are_keyed_by_hash_then_url() {
+        let mut recipe = Recipe {
+            image_url: Some("https://example.com/chana.jpg".into()),
+            ..Default::default()
+        };
+        let by_url = photo_key(&recipe).unwrap();
+        assert_eq!(by_url.len(), 64);
+
+        recipe.photo_hash = Some("ABCDEF".into());
+        assert_eq!(photo_key(&recipe), Some("abcdef".into()));
+
+        // anything else could name a file outside the photo directory
+        for hash in &["../../x", "/etc/passwd", "abc/def"] {
+            recipe.photo_hash = Some(hash.to_string());
+            assert_eq!(photo_key(&recipe), Some(by_url.clone()));
+        }
+
+        recipe.photo_hash = None;
+        recipe.image_url = None;
+        assert_eq!(photo_key(&recipe), None);
+    }
+
+    #[test]
+    fn garbage_is_not_cached() {
+        let dir = tempfile::tempdir().unwrap();
+
+        assert!(matches!(
+            store(dir.path(), "bad", b"<html>not found</html>"),
+            Err(Error::Parse(_))
+        ));
+        assert!(cached(dir.path(), "bad").is_none());
+    }
+}
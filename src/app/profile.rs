@@ -0,0 +1,114 @@
+//! Named profiles, so several people can keep their own accounts on one machine.
+//!
+//! Every profile has its own backend, saved login, recipe database and cache. Its files live
+//! under `profiles/<id>` in the data and cache directories, so nothing is shared between them but
+//! the settings.
+
+use crate::app::backend::BackendConfig;
+use crate::app::error::Result;
+use crate::app::paths::{self, Paths};
+use crate::app::{category, credentials, photo_cache, recipe_db};
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use uuid::Uuid;
+
+/// The profile everyone starts with, which inherits whatever was stored before profiles existed
+pub const DEFAULT_ID: &str = "default";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Profile {
+    /// Names the profile's directories, so it stays the same through renames
+    pub id: String,
+    pub name: String,
+    pub backend: BackendConfig,
+}
+
+impl Profile {
+    pub fn new(name: String, backend: BackendConfig) -> Self {
+        Self {
+            id: Uuid::new_v4().to_simple().to_string(),
+            name,
+            backend,
+        }
+    }
+
+    pub fn default_profile() -> Self {
+        Self {
+            id: DEFAULT_ID.into(),
+            name: "Default".into(),
+            backend: BackendConfig::from_env(),
+        }
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// Moves the database, login and cache older versions kept straight in the data and cache
+/// directories into the default profile's. Anything the profile already has is left alone.
+pub fn migrate_unprofiled(root: &Paths, default: &Paths) -> Result<()> {
+    let moves = [
+        (
+            recipe_db::db_file(&root.data_dir),
+            recipe_db::db_file(&default.data_dir),
+        ),
+        (
+            credentials::credentials_file(&root.data_dir),
+            credentials::credentials_file(&default.data_dir),
+        ),
+        (
+            category::cache_file(&root.cache_dir),
+            category::cache_file(&default.cache_dir),
+        ),
+        (
+            photo_cache::photo_dir(&root.cache_dir),
+            photo_cache::photo_dir(&default.cache_dir),
+        ),
+    ];
+    for (from, to) in moves.iter() {
+        paths::move_path(from, to)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn old_files_move_into_the_default_profile() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = Paths {
+            config_dir: dir.path().join("config"),
+            cache_dir: dir.path().join("cache"),
+            data_dir: dir.path().join("data"),
+        };
+        fs::create_dir_all(photo_cache::photo_dir(&root.cache_dir)).unwrap();
+        fs::write(photo_cache::photo_dir(&root.cache_dir).join("abc"), "jpeg").unwrap();
+        fs::write(category::cache_file(&root.cache_dir), "[]").unwrap();
+        fs::create_dir_all(&root.data_dir).unwrap();
+        fs::write(recipe_db::db_file(&root.data_dir), "db").unwrap();
+
+        let default = root.for_profile(DEFAULT_ID);
+        migrate_unprofiled(&root, &default).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(recipe_db::db_file(&default.data_dir)).unwrap(),
+            "db"
+        );
+        assert!(category::cache_file(&default.cache_dir).is_file());
+        assert!(photo_cache::photo_dir(&default.cache_dir)
+            .join("abc")
+            .is_file());
+        assert!(!recipe_db::db_file(&root.data_dir).exists());
+
+        // a second profile starts out empty
+        let other = root.for_profile("other");
+        assert!(!recipe_db::db_file(&other.data_dir).exists());
+    }
+}
@@ -0,0 +1,208 @@
+use crate::app::backend::BackendConfig;
+use crate::app::profile::Profile;
+use crate::app::theme::Theme;
+use crate::app::Message;
+
+use iced::{
+    button, pane_grid, pick_list, text_input, Align, Button, Column, Element, Length, PickList,
+    Row, Text, TextInput,
+};
+use std::fmt;
+
+const LABEL_WIDTH: u16 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackendKind {
+    PaprikaCloud,
+    Server,
+    LocalDirectory,
+}
+
+impl BackendKind {
+    pub const ALL: [BackendKind; 3] = [
+        BackendKind::PaprikaCloud,
+        BackendKind::Server,
+        BackendKind::LocalDirectory,
+    ];
+
+    /// Which kind `config` is, and its server URL or folder
+    pub fn of(config: &BackendConfig) -> (Self, String) {
+        match config {
+            BackendConfig::PaprikaCloud => (BackendKind::PaprikaCloud, String::new()),
+            BackendConfig::Server { url } => (BackendKind::Server, url.clone()),
+            BackendConfig::LocalDirectory { path } => {
+                (BackendKind::LocalDirectory, path.display().to_string())
+            }
+        }
+    }
+
+    /// The backend at `location`, if it's been filled in where one is needed
+    pub fn config(self, location: &str) -> Option<BackendConfig> {
+        let location = location.trim();
+        match self {
+            BackendKind::PaprikaCloud => Some(BackendConfig::PaprikaCloud),
+            _ if location.is_empty() => None,
+            BackendKind::Server => Some(BackendConfig::Server {
+                url: location.into(),
+            }),
+            BackendKind::LocalDirectory => Some(BackendConfig::LocalDirectory {
+                path: location.into(),
+            }),
+        }
+    }
+
+    /// What to ask for the location with, as a label and an example
+    pub fn location_label(self) -> Option<(&'static str, &'static str)> {
+        match self {
+            BackendKind::PaprikaCloud => None,
+            BackendKind::Server => Some(("Server URL", "https://example.com/api/v2")),
+            BackendKind::LocalDirectory => Some(("Folder", "/home/me/recipes")),
+        }
+    }
+}
+
+impl fmt::Display for BackendKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                BackendKind::PaprikaCloud => "Paprika cloud",
+                BackendKind::Server => "Paprika-compatible server",
+                BackendKind::LocalDirectory => "Folder on this computer",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ProfileMessage {
+    NameChanged(String),
+    BackendPicked(BackendKind),
+    /// The server URL or folder, depending on the backend
+    LocationChanged(String),
+}
+
+/// Sets up a new profile
+pub struct ProfileForm {
+    name: String,
+    backend: BackendKind,
+    location: String,
+
+    name_input: text_input::State,
+    backend_picker: pick_list::State<BackendKind>,
+    location_input: text_input::State,
+    create: button::State,
+    cancel: button::State,
+}
+
+impl ProfileForm {
+    pub fn new() -> Self {
+        Self {
+            name: String::new(),
+            backend: BackendKind::PaprikaCloud,
+            location: String::new(),
+            name_input: text_input::State::focused(),
+            backend_picker: pick_list::State::default(),
+            location_input: text_input::State::new(),
+            create: button::State::new(),
+            cancel: button::State::new(),
+        }
+    }
+
+    pub fn update(&mut self, message: ProfileMessage) {
+        match message {
+            ProfileMessage::NameChanged(name) => self.name = name,
+            ProfileMessage::BackendPicked(backend) => self.backend = backend,
+            ProfileMessage::LocationChanged(location) => self.location = location,
+        }
+    }
+
+    /// The profile as filled in so far, if it's complete
+    pub fn to_profile(&self) -> Option<Profile> {
+        let name = self.name.trim();
+        if name.is_empty() {
+            return None;
+        }
+
+        let backend = self.backend.config(&self.location)?;
+        Some(Profile::new(name.into(), backend))
+    }
+
+    pub fn view(&mut self, pane: pane_grid::Pane, theme: Theme) -> Element<'_, Message> {
+        let complete = self.to_profile().is_some();
+        let ProfileForm {
+            name,
+            backend,
+            location,
+            name_input,
+            backend_picker,
+            location_input,
+            create,
+            cancel,
+        } = self;
+
+        let row = |label: &str| {
+            Row::new()
+                .spacing(10)
+                .align_items(Align::Center)
+                .push(Text::new(label).width(Length::Units(LABEL_WIDTH)))
+        };
+
+        let mut content = Column::new()
+            .padding(10)
+            .spacing(15)
+            .push(Text::new("New profile").size(28))
+            .push(Text::new(
+                "Each profile has its own login, recipes and cache.",
+            ))
+            .push(
+                row("Name").push(
+                    TextInput::new(name_input, "", name, move |name| {
+                        Message::ProfileForm(pane, ProfileMessage::NameChanged(name))
+                    })
+                    .style(theme)
+                    .padding(5),
+                ),
+            )
+            .push(
+                row("Sync with").push(
+                    PickList::new(
+                        backend_picker,
+                        &BackendKind::ALL[..],
+                        Some(*backend),
+                        move |backend| {
+                            Message::ProfileForm(pane, ProfileMessage::BackendPicked(backend))
+                        },
+                    )
+                    .style(theme),
+                ),
+            );
+
+        if let Some((label, placeholder)) = backend.location_label() {
+            content = content.push(
+                row(label).push(
+                    TextInput::new(location_input, placeholder, location, move |location| {
+                        Message::ProfileForm(pane, ProfileMessage::LocationChanged(location))
+                    })
+                    .style(theme)
+                    .padding(5),
+                ),
+            );
+        }
+
+        let mut create_button = Button::new(create, Text::new("Create profile")).style(theme);
+        if complete {
+            create_button = create_button.on_press(Message::ProfileCreated(pane));
+        }
+        content
+            .push(
+                Row::new().spacing(10).push(create_button).push(
+                    Button::new(cancel, Text::new("Cancel"))
+                        .style(theme)
+                        .on_press(Message::Close(pane)),
+                ),
+            )
+            .into()
+    }
+}
@@ -1,54 +1,209 @@
+use crate::app::category::{self, Category};
+use crate::app::photo_cache::{self, Photo};
+use crate::app::recipe_db::SyncState;
+use crate::app::theme::Theme;
 use crate::app::Message;
-use paprika_api;
 
-use iced::{button, Button, Column, Element, Svg, Text};
+use iced::{
+    button, image, pane_grid, scrollable, Align, Button, Column, Element, Length, Row, Scrollable,
+    Svg, Text,
+};
 
+const PHOTO_HEIGHT: u16 = 200;
+
+/// A read-only view of a whole recipe, laid out for cooking from
 pub struct RecipeView {
     pub recipe: paprika_api::api::Recipe,
-    pub state: button::State,
-    recipe_interior: RecipeInterior,
-}
-
-struct RecipeInterior {
-    text: Text,
-    image: Svg,
+    scroll: scrollable::State,
+    edit: button::State,
+    trash: button::State,
+    shopping_list: button::State,
+    source_link: button::State,
+    resolve: button::State,
 }
 
-impl RecipeInterior {
-    fn new(text: String, image_path: &str) -> Self {
+impl RecipeView {
+    pub fn new(recipe: paprika_api::api::Recipe) -> Self {
         Self {
-            text: Text::new(text),
-            image: Svg::from_path(image_path),
+            recipe,
+            scroll: scrollable::State::new(),
+            edit: button::State::new(),
+            trash: button::State::new(),
+            shopping_list: button::State::new(),
+            source_link: button::State::new(),
+            resolve: button::State::new(),
         }
     }
 
-    pub fn view(&mut self) -> Element<Message> {
-        let mut column = Column::new();
-        // TODO: Why do I have to clone these?
-        column = column.push(self.image.clone());
-        column = column.push(self.text.clone());
-        column.into()
-    }
-}
+    /// `categories` are used to name the recipe's categories; `photo` is shown once downloaded.
+    /// `sync_state` says whether the recipe has local changes still to upload or merge.
+    pub fn view(
+        &mut self,
+        pane: pane_grid::Pane,
+        categories: &[Category],
+        photo: Option<&Photo>,
+        sync_state: SyncState,
+        theme: Theme,
+    ) -> Element<'_, Message> {
+        let recipe = &self.recipe;
+        let category_names: Vec<&str> = recipe
+            .categories
+            .iter()
+            .map(|uid| category::name_of(categories, uid))
+            .collect();
 
-impl RecipeView {
-    pub fn new(recipe: paprika_api::api::Recipe) -> Self {
-        let name = recipe.name.clone();
-        Self {
-            recipe: recipe,
-            state: button::State::new(),
-            recipe_interior: RecipeInterior::new(name, "resources/recipe.svg"),
+        let trash = match recipe.in_trash {
+            true => Button::new(&mut self.trash, Text::new("Restore"))
+                .style(theme)
+                .on_press(Message::RestoreRecipeClicked(pane, recipe.uid.clone())),
+            false => Button::new(&mut self.trash, Text::new("Move to trash"))
+                .style(theme)
+                .on_press(Message::TrashRecipeClicked(pane, recipe.uid.clone())),
+        };
+        let shopping_list = match recipe.on_grocery_list {
+            true => "Remove from shopping list",
+            false => "Add to shopping list",
+        };
+        let header = Row::new()
+            .spacing(10)
+            .push(Text::new(&recipe.name).size(28).width(Length::Fill))
+            .push(
+                Button::new(&mut self.edit, Text::new("Edit"))
+                    .style(theme)
+                    .on_press(Message::EditRecipeClicked(recipe.uid.clone())),
+            )
+            .push(
+                Button::new(&mut self.shopping_list, Text::new(shopping_list))
+                    .style(theme)
+                    .on_press(Message::ShoppingListToggled(pane, recipe.uid.clone())),
+            )
+            .push(trash);
+
+        let photo: Element<'_, Message> = match photo {
+            Some(photo) => image::Image::new(image::Handle::from_path(&photo.full))
+                .width(Length::Shrink)
+                .height(Length::Units(PHOTO_HEIGHT))
+                .into(),
+            None => Svg::new(photo_cache::placeholder())
+                .width(Length::Shrink)
+                .height(Length::Units(PHOTO_HEIGHT))
+                .into(),
+        };
+
+        let mut content = Scrollable::new(&mut self.scroll)
+            .style(theme)
+            .width(Length::Fill)
+            .padding(10)
+            .spacing(15)
+            .push(header);
+
+        if recipe.in_trash {
+            content = content.push(Text::new("In the trash").size(16));
         }
-    }
 
-    pub fn view(&mut self) -> Element<Message> {
-        let mut column = Column::new();
+        match sync_state {
+            SyncState::Synced => (),
+            SyncState::Conflicted => {
+                content = content.push(
+                    Row::new()
+                        .spacing(10)
+                        .align_items(Align::Center)
+                        .push(Text::new("Also changed on another device").size(16))
+                        .push(
+                            Button::new(&mut self.resolve, Text::new("Merge"))
+                                .style(theme)
+                                .on_press(Message::ResolveConflictClicked(recipe.uid.clone())),
+                        ),
+                )
+            }
+            SyncState::Modified | SyncState::Deleted | SyncState::Purged => {
+                content =
+                    content.push(Text::new("Saved on this device only; not synced yet").size(16))
+            }
+        }
+        content = content.push(photo);
+
+        let metadata = [
+            ("Rating", stars(recipe.rating)),
+            ("Servings", recipe.servings.clone()),
+            ("Difficulty", recipe.difficulty.clone()),
+            ("Prep time", recipe.prep_time.clone()),
+            ("Cook time", recipe.cook_time.clone()),
+            ("Total time", recipe.total_time.clone()),
+            ("Categories", category_names.join(", ")),
+            ("Added", recipe.created.clone()),
+        ];
+        let mut metadata_column = Column::new().spacing(2);
+        for (label, value) in metadata.iter().filter(|(_, value)| !value.is_empty()) {
+            metadata_column = metadata_column.push(Text::new(format!("{}: {}", label, value)));
+        }
+        content = content.push(metadata_column);
+
+        if !recipe.description.trim().is_empty() {
+            content = content.push(Text::new(&recipe.description));
+        }
+
+        let ingredients = recipe
+            .ingredients
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| format!("• {}", line.trim()));
+        content = content.push(section("Ingredients", ingredients));
+
+        let directions = recipe
+            .directions
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .enumerate()
+            .map(|(i, line)| format!("{}. {}", i + 1, line.trim()));
+        content = content.push(section("Directions", directions));
 
-        let test_button = Button::new(&mut self.state, self.recipe_interior.view())
-            .on_press(Message::RecipeClicked(self.recipe.clone()));
+        if !recipe.notes.trim().is_empty() {
+            content = content.push(section("Notes", recipe.notes.lines().map(String::from)));
+        }
 
-        column = column.push(test_button);
+        if !recipe.nutritional_info.trim().is_empty() {
+            content = content.push(section(
+                "Nutrition",
+                recipe.nutritional_info.lines().map(String::from),
+            ));
+        }
+
+        let source = match &recipe.source_url {
+            Some(url) if !url.is_empty() => {
+                let label = if recipe.source.is_empty() {
+                    url.clone()
+                } else {
+                    recipe.source.clone()
+                };
+                let link = Button::new(&mut self.source_link, Text::new(label))
+                    .style(theme)
+                    .on_press(Message::OpenUrl(url.clone()));
+                Some(Row::new().spacing(5).push(Text::new("Source:")).push(link))
+            }
+            _ if !recipe.source.is_empty() => {
+                Some(Row::new().push(Text::new(format!("Source: {}", recipe.source))))
+            }
+            _ => None,
+        };
+        if let Some(source) = source {
+            content = content.push(source);
+        }
+
+        content.into()
+    }
+}
+
+fn section<'a>(title: &str, lines: impl Iterator<Item = String>) -> Column<'a, Message> {
+    lines.fold(
+        Column::new().spacing(5).push(Text::new(title).size(22)),
+        |column, line| column.push(Text::new(line)),
+    )
+}
 
-        column.into()
+pub fn stars(rating: i32) -> String {
+    if rating <= 0 {
+        return String::new();
     }
+    "★".repeat(rating as usize)
 }
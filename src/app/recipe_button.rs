@@ -1,28 +1,51 @@
+use crate::app::photo_cache::{self, Photo};
+use crate::app::theme::Theme;
 use crate::app::Message;
 
-#[allow(unused)]
-use iced::{button, Button, Element, Text};
+use iced::{button, image, Align, Button, Element, Length, Row, Svg, Text};
+
+const THUMBNAIL_SIZE: u16 = 48;
 
 pub struct RecipeButton {
     name: String,
     recipe_uid: String,
-    #[allow(unused)]
-    image_url: String,
+    thumbnail: Option<Photo>,
     pub state: button::State,
 }
 
 impl RecipeButton {
-    pub fn new(name: String, recipe_uid: String, image_url: String) -> Self {
+    pub fn new(name: String, recipe_uid: String, thumbnail: Option<Photo>) -> Self {
         RecipeButton {
-            name: name,
-            recipe_uid: recipe_uid,
-            image_url: image_url,
-            state: button::State::new()
+            name,
+            recipe_uid,
+            thumbnail,
+            state: button::State::new(),
         }
     }
 
-    pub fn view(&mut self) -> Element<Message> {
-        Button::new(&mut self.state, Text::new(&self.name)).on_press(Message::RecipeClicked(self.recipe_uid.clone())).into()
+    pub fn view(&mut self, theme: Theme) -> Element<'_, Message> {
+        let size = Length::Units(THUMBNAIL_SIZE);
+        let thumbnail: Element<'_, Message> = match &self.thumbnail {
+            Some(photo) => image::Image::new(image::Handle::from_path(&photo.thumbnail))
+                .width(size)
+                .height(size)
+                .into(),
+            None => Svg::new(photo_cache::placeholder())
+                .width(size)
+                .height(size)
+                .into(),
+        };
+
+        let content = Row::new()
+            .spacing(10)
+            .align_items(Align::Center)
+            .push(thumbnail)
+            .push(Text::new(&self.name));
+
+        Button::new(&mut self.state, content)
+            .style(theme)
+            .on_press(Message::RecipeClicked(self.recipe_uid.clone()))
+            .into()
         //Text::new(format!("{}", &self.name)).into()
     }
 }
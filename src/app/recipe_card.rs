@@ -0,0 +1,109 @@
+use crate::app::photo_cache::{self, Photo};
+use crate::app::recipe;
+use crate::app::theme::Theme;
+use crate::app::Message;
+
+use iced::{button, image, Align, Button, Column, Element, Length, Svg, Text};
+use serde::{Deserialize, Serialize};
+
+pub const CARD_WIDTH: u16 = 180;
+pub const CARD_SPACING: u16 = 10;
+const PHOTO_HEIGHT: u16 = 120;
+
+/// How the recipe list shows its recipes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ListMode {
+    /// A column of names with thumbnails
+    #[default]
+    List,
+    /// Cards with a photo, filling the width of the pane
+    Grid,
+}
+
+impl ListMode {
+    pub fn toggled(self) -> Self {
+        match self {
+            ListMode::List => ListMode::Grid,
+            ListMode::Grid => ListMode::List,
+        }
+    }
+}
+
+/// How many cards fit side by side in `width`, always at least one
+pub fn columns(width: f32) -> usize {
+    let spacing = f32::from(CARD_SPACING);
+    ((width + spacing) / (f32::from(CARD_WIDTH) + spacing)).max(1.0) as usize
+}
+
+/// A recipe in the grid: its photo, name, rating and total time
+pub struct RecipeCard {
+    name: String,
+    recipe_uid: String,
+    rating: i32,
+    total_time: String,
+    photo: Option<Photo>,
+    state: button::State,
+}
+
+impl RecipeCard {
+    pub fn new(recipe: &paprika_api::api::Recipe, photo: Option<Photo>) -> Self {
+        RecipeCard {
+            name: recipe.name.clone(),
+            recipe_uid: recipe.uid.clone(),
+            rating: recipe.rating,
+            total_time: recipe.total_time.trim().to_string(),
+            photo,
+            state: button::State::new(),
+        }
+    }
+
+    pub fn view(&mut self, theme: Theme) -> Element<'_, Message> {
+        let width = Length::Fill;
+        let height = Length::Units(PHOTO_HEIGHT);
+        let photo: Element<'_, Message> = match &self.photo {
+            Some(photo) => image::Image::new(image::Handle::from_path(&photo.card))
+                .width(width)
+                .height(height)
+                .into(),
+            None => Svg::new(photo_cache::placeholder())
+                .width(width)
+                .height(height)
+                .into(),
+        };
+
+        let mut content = Column::new()
+            .spacing(5)
+            .align_items(Align::Center)
+            .push(photo)
+            .push(Text::new(&self.name).size(18));
+        let rating = recipe::stars(self.rating);
+        if !rating.is_empty() {
+            content = content.push(Text::new(rating).size(16));
+        }
+        if !self.total_time.is_empty() {
+            content = content.push(Text::new(&self.total_time).size(16));
+        }
+
+        Button::new(&mut self.state, content)
+            .style(theme)
+            .width(Length::Units(CARD_WIDTH))
+            .padding(5)
+            .on_press(Message::RecipeClicked(self.recipe_uid.clone()))
+            .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cards_reflow_with_the_width() {
+        assert_eq!(columns(0.0), 1);
+        assert_eq!(columns(f32::from(CARD_WIDTH)), 1);
+        assert_eq!(columns(f32::from(2 * CARD_WIDTH + CARD_SPACING)), 2);
+        assert_eq!(columns(f32::from(2 * CARD_WIDTH + CARD_SPACING) - 1.0), 1);
+        assert_eq!(columns(1000.0), 5);
+    }
+}
@@ -0,0 +1,625 @@
+use crate::app::error::{Error, Result};
+
+use paprika_api::api::Recipe;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::{fs, path};
+
+const DB_FILE: &str = "recipes.sqlite3";
+
+/// Schema changes, applied in order; `PRAGMA user_version` records how many have run
+const MIGRATIONS: &[&str] = &[
+    "
+    CREATE TABLE recipes (
+        uid TEXT PRIMARY KEY,
+        hash TEXT NOT NULL,
+        recipe TEXT NOT NULL,
+        sync_state INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL
+    );
+    ",
+    "
+    ALTER TABLE recipes ADD COLUMN attempts INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE recipes ADD COLUMN retry_at INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE recipes ADD COLUMN last_error TEXT;
+    ",
+    "
+    ALTER TABLE recipes ADD COLUMN base TEXT;
+    ALTER TABLE recipes ADD COLUMN theirs TEXT;
+    ",
+    "
+    CREATE TABLE meta (
+        key TEXT PRIMARY KEY,
+        value INTEGER NOT NULL
+    );
+    ",
+];
+
+/// Whether the copy of a recipe on this machine matches the backend's
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncState {
+    Synced,
+    /// Created or edited here and not uploaded yet
+    Modified,
+    /// Deleted here; the row stays until the deletion is uploaded
+    Deleted,
+    /// Edited here while the server copy changed too; waits for the edits to be merged
+    Conflicted,
+    /// Removed from this device. Backends that only trash recipes still list it, so the row stays
+    /// to keep it from being downloaded again, unless it's restored from the trash elsewhere.
+    Purged,
+}
+
+impl SyncState {
+    fn to_sql(self) -> i64 {
+        match self {
+            SyncState::Synced => 0,
+            SyncState::Modified => 1,
+            SyncState::Deleted => 2,
+            SyncState::Conflicted => 3,
+            SyncState::Purged => 4,
+        }
+    }
+
+    fn from_sql(value: i64) -> Self {
+        match value {
+            1 => SyncState::Modified,
+            2 => SyncState::Deleted,
+            3 => SyncState::Conflicted,
+            4 => SyncState::Purged,
+            _ => SyncState::Synced,
+        }
+    }
+}
+
+/// Every recipe we know about, kept on disk so Ancho works without a connection.
+///
+/// Recipes that aren't `Synced` make up the outbox: changes made here that still have to be
+/// uploaded. Each remembers how often its upload failed and when to try again, and the server
+/// copy the change started from, so changes made elsewhere in the meantime can be merged.
+pub struct RecipeDb {
+    connection: Connection,
+}
+
+pub fn db_file(data_dir: &path::Path) -> path::PathBuf {
+    data_dir.join(DB_FILE)
+}
+
+fn to_timestamp(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+fn from_timestamp(seconds: i64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(seconds.max(0) as u64)
+}
+
+impl RecipeDb {
+    pub fn open(path: &path::Path) -> Result<Self> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+
+        Self::migrate(Connection::open(path)?)
+    }
+
+    /// A database that only lives as long as the app, for when the real one can't be opened
+    pub fn open_in_memory() -> Result<Self> {
+        Self::migrate(Connection::open_in_memory()?)
+    }
+
+    fn migrate(connection: Connection) -> Result<Self> {
+        let version: usize =
+            connection.query_row("PRAGMA user_version", [], |row| row.get::<_, i64>(0))? as usize;
+
+        for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            connection.execute_batch(migration)?;
+            connection.pragma_update(None, "user_version", (i + 1) as i64)?;
+        }
+
+        Ok(Self { connection })
+    }
+
+    pub fn recipes(&self) -> Result<Vec<Recipe>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT recipe FROM recipes WHERE sync_state NOT IN (?1, ?2)")?;
+        let rows = statement.query_map(
+            params![SyncState::Deleted.to_sql(), SyncState::Purged.to_sql()],
+            |row| row.get::<_, String>(0),
+        )?;
+
+        let mut recipes = Vec::new();
+        for row in rows {
+            recipes.push(serde_json::from_str(&row?)?);
+        }
+        Ok(recipes)
+    }
+
+    pub fn recipe(&self, uid: &str) -> Result<Option<Recipe>> {
+        self.recipe_column(uid, "recipe")
+    }
+
+    /// The server copy a local edit started from, if the recipe has local edits
+    pub fn base(&self, uid: &str) -> Result<Option<Recipe>> {
+        self.recipe_column(uid, "base")
+    }
+
+    /// A recipe's base, local and server copies, if it's waiting to be merged
+    pub fn conflict(&self, uid: &str) -> Result<Option<(Option<Recipe>, Recipe, Recipe)>> {
+        match (self.recipe(uid)?, self.recipe_column(uid, "theirs")?) {
+            (Some(mine), Some(theirs)) => Ok(Some((self.base(uid)?, mine, theirs))),
+            _ => Ok(None),
+        }
+    }
+
+    /// Reads one of the recipe JSON columns
+    fn recipe_column(&self, uid: &str, column: &str) -> Result<Option<Recipe>> {
+        let json: Option<Option<String>> = self
+            .connection
+            .query_row(
+                &format!("SELECT {} FROM recipes WHERE uid = ?1", column),
+                params![uid],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        Ok(match json.flatten() {
+            Some(json) => Some(serde_json::from_str(&json)?),
+            None => None,
+        })
+    }
+
+    /// The stored hash and sync state of a recipe, if we have it
+    pub fn status(&self, uid: &str) -> Result<Option<(String, SyncState)>> {
+        Ok(self
+            .connection
+            .query_row(
+                "SELECT hash, sync_state FROM recipes WHERE uid = ?1",
+                params![uid],
+                |row| Ok((row.get(0)?, SyncState::from_sql(row.get(1)?))),
+            )
+            .optional()?)
+    }
+
+    /// The hash and sync state of every stored recipe, by uid
+    pub fn statuses(&self) -> Result<HashMap<String, (String, SyncState)>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT uid, hash, sync_state FROM recipes")?;
+        let rows = statement.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                (row.get(1)?, SyncState::from_sql(row.get(2)?)),
+            ))
+        })?;
+
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Uids of recipes waiting for local and remote edits to be merged
+    pub fn conflicted(&self) -> Result<Vec<String>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT uid FROM recipes WHERE sync_state = ?1")?;
+        let rows =
+            statement.query_map(params![SyncState::Conflicted.to_sql()], |row| row.get(0))?;
+
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Uids of recipes with local changes that haven't been uploaded
+    pub fn unsynced(&self) -> Result<Vec<String>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT uid FROM recipes WHERE sync_state NOT IN (?1, ?2)")?;
+        let rows = statement.query_map(
+            params![SyncState::Synced.to_sql(), SyncState::Purged.to_sql()],
+            |row| row.get(0),
+        )?;
+
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Local changes that are due to be uploaded at `now`, oldest first
+    pub fn pending(&self, now: SystemTime) -> Result<Vec<(String, SyncState)>> {
+        let mut statement = self.connection.prepare(
+            "SELECT uid, sync_state FROM recipes
+             WHERE sync_state IN (?1, ?2) AND retry_at <= ?3
+             ORDER BY updated_at",
+        )?;
+        let rows = statement.query_map(
+            params![
+                SyncState::Modified.to_sql(),
+                SyncState::Deleted.to_sql(),
+                to_timestamp(now)
+            ],
+            |row| Ok((row.get(0)?, SyncState::from_sql(row.get(1)?))),
+        )?;
+
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Records a failed upload; `backoff` turns the number of failures so far into how long to
+    /// wait before the next try
+    pub fn mark_failed(
+        &self,
+        uid: &str,
+        error: &Error,
+        backoff: fn(u32) -> Duration,
+    ) -> Result<()> {
+        let attempts: u32 = self.connection.query_row(
+            "SELECT attempts FROM recipes WHERE uid = ?1",
+            params![uid],
+            |row| row.get(0),
+        )?;
+        let attempts = attempts + 1;
+        let retry_at = SystemTime::now() + backoff(attempts);
+
+        self.connection.execute(
+            "UPDATE recipes SET attempts = ?2, retry_at = ?3, last_error = ?4 WHERE uid = ?1",
+            params![uid, attempts, to_timestamp(retry_at), error.to_string()],
+        )?;
+        Ok(())
+    }
+
+    /// Sets a local edit aside until it's merged with `theirs`, the newer server copy
+    pub fn mark_conflict(&self, uid: &str, theirs: &Recipe) -> Result<()> {
+        self.connection.execute(
+            "UPDATE recipes SET sync_state = ?2, theirs = ?3 WHERE uid = ?1",
+            params![
+                uid,
+                SyncState::Conflicted.to_sql(),
+                serde_json::to_string(theirs)?
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Replaces a conflicted recipe with the merge of both edits, queued for upload on top of
+    /// `theirs`
+    pub fn resolve(&self, merged: &Recipe, theirs: &Recipe) -> Result<()> {
+        self.write(
+            merged,
+            SyncState::Modified,
+            to_timestamp(SystemTime::now()),
+            Some(serde_json::to_string(theirs)?),
+        )
+    }
+
+    /// Hides a recipe and queues its deletion for upload
+    pub fn delete_locally(&self, uid: &str) -> Result<()> {
+        self.connection.execute(
+            "UPDATE recipes
+             SET sync_state = ?2, attempts = 0, retry_at = 0, last_error = NULL
+             WHERE uid = ?1",
+            params![uid, SyncState::Deleted.to_sql()],
+        )?;
+        Ok(())
+    }
+
+    /// When each recipe last changed, as far as this machine has seen
+    pub fn updated_times(&self) -> Result<HashMap<String, SystemTime>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT uid, updated_at FROM recipes")?;
+        let rows = statement.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, from_timestamp(row.get(1)?)))
+        })?;
+
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    pub fn updated_at(&self, uid: &str) -> Result<Option<SystemTime>> {
+        Ok(self
+            .connection
+            .query_row(
+                "SELECT updated_at FROM recipes WHERE uid = ?1",
+                params![uid],
+                |row| row.get(0),
+            )
+            .optional()?
+            .map(from_timestamp))
+    }
+
+    /// Stores `recipe`, stamping it as changed now if its hash differs from the stored one.
+    ///
+    /// The first local edit of a synced recipe remembers the stored copy as its base.
+    pub fn save(&self, recipe: &Recipe, state: SyncState) -> Result<()> {
+        self.store(recipe, state, None)
+    }
+
+    /// Stores an edit made starting from `opened`. If the recipe was synced, `opened` becomes
+    /// the base rather than the stored copy, so a download that landed while the edit was going
+    /// on still counts as a change made elsewhere.
+    pub fn save_edit(&self, recipe: &Recipe, opened: &Recipe) -> Result<()> {
+        self.store(recipe, SyncState::Modified, Some(opened))
+    }
+
+    fn store(&self, recipe: &Recipe, state: SyncState, opened: Option<&Recipe>) -> Result<()> {
+        let status = self.status(&recipe.uid)?;
+        let base = match (state, &status) {
+            (SyncState::Synced, _) | (_, None) => None,
+            (_, Some((_, SyncState::Synced))) => match opened {
+                Some(opened) => Some(opened.clone()),
+                None => self.recipe(&recipe.uid)?,
+            },
+            (_, Some(_)) => self.base(&recipe.uid)?,
+        };
+        let base = match base {
+            Some(base) => Some(serde_json::to_string(&base)?),
+            None => None,
+        };
+
+        let changed = status.is_none_or(|(hash, _)| hash != recipe.hash);
+        let updated_at = if changed {
+            to_timestamp(SystemTime::now())
+        } else {
+            self.connection.query_row(
+                "SELECT updated_at FROM recipes WHERE uid = ?1",
+                params![recipe.uid],
+                |row| row.get(0),
+            )?
+        };
+
+        self.write(recipe, state, updated_at, base)
+    }
+
+    fn write(
+        &self,
+        recipe: &Recipe,
+        state: SyncState,
+        updated_at: i64,
+        base: Option<String>,
+    ) -> Result<()> {
+        self.connection.execute(
+            "INSERT INTO recipes (uid, hash, recipe, sync_state, updated_at, base)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT (uid) DO UPDATE SET
+                hash = excluded.hash,
+                recipe = excluded.recipe,
+                sync_state = excluded.sync_state,
+                updated_at = excluded.updated_at,
+                base = excluded.base,
+                theirs = NULL,
+                attempts = 0,
+                retry_at = 0,
+                last_error = NULL",
+            params![
+                recipe.uid,
+                recipe.hash,
+                serde_json::to_string(recipe)?,
+                state.to_sql(),
+                updated_at,
+                base
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// When a sync pass last ran to the end
+    pub fn last_synced(&self) -> Result<Option<SystemTime>> {
+        Ok(self
+            .connection
+            .query_row(
+                "SELECT value FROM meta WHERE key = 'last_synced'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?
+            .map(from_timestamp))
+    }
+
+    pub fn set_last_synced(&self, time: SystemTime) -> Result<()> {
+        self.connection.execute(
+            "INSERT INTO meta (key, value) VALUES ('last_synced', ?1)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            params![to_timestamp(time)],
+        )?;
+        Ok(())
+    }
+
+    /// Forgets every recipe, synced or not, and when they were synced
+    pub fn clear(&self) -> Result<()> {
+        self.connection
+            .execute_batch("DELETE FROM recipes; DELETE FROM meta;")?;
+        Ok(())
+    }
+
+    /// Records that a deletion was uploaded, keeping just enough to not download it again
+    pub fn purge(&self, uid: &str) -> Result<()> {
+        self.connection.execute(
+            "UPDATE recipes
+             SET sync_state = ?2, base = NULL, theirs = NULL, last_error = NULL
+             WHERE uid = ?1",
+            params![uid, SyncState::Purged.to_sql()],
+        )?;
+        Ok(())
+    }
+
+    pub fn remove(&self, uid: &str) -> Result<()> {
+        self.connection
+            .execute("DELETE FROM recipes WHERE uid = ?1", params![uid])?;
+        Ok(())
+    }
+
+    /// Pulls recipes out of the one-file-per-recipe cache older versions kept in `cache_dir`,
+    /// removing each file once it's stored. Does nothing once the database has recipes.
+    pub fn import_json_cache(&self, cache_dir: &path::Path) -> Result<usize> {
+        let count: i64 = self
+            .connection
+            .query_row("SELECT COUNT(*) FROM recipes", [], |row| row.get(0))?;
+        if count > 0 || !cache_dir.is_dir() {
+            return Ok(0);
+        }
+
+        let mut imported = 0;
+        for entry in fs::read_dir(cache_dir)? {
+            let path = entry?.path();
+            // recipe files were named after their uid, with no extension
+            if !path.is_file() || path.extension().is_some() {
+                continue;
+            }
+
+            let recipe: Recipe = match fs::read_to_string(&path)
+                .ok()
+                .and_then(|json| serde_json::from_str(&json).ok())
+            {
+                Some(recipe) => recipe,
+                None => continue,
+            };
+            let updated_at = fs::metadata(&path)
+                .and_then(|metadata| metadata.modified())
+                .map(to_timestamp)
+                .unwrap_or(0);
+
+            self.write(&recipe, SyncState::Synced, updated_at, None)?;
+            fs::remove_file(&path)?;
+            imported += 1;
+        }
+
+        Ok(imported)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::backend::mock_server;
+
+    #[test]
+    fn recipes_survive_reopening() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = db_file(dir.path());
+
+        {
+            let db = RecipeDb::open(&path).unwrap();
+            assert_eq!(db.last_synced().unwrap(), None);
+            for recipe in mock_server::recipes() {
+                db.save(&recipe, SyncState::Synced).unwrap();
+            }
+            db.set_last_synced(from_timestamp(1_600_000_000)).unwrap();
+        }
+
+        let db = RecipeDb::open(&path).unwrap();
+        assert_eq!(
+            db.last_synced().unwrap(),
+            Some(from_timestamp(1_600_000_000))
+        );
+        assert_eq!(db.recipes().unwrap().len(), mock_server::recipes().len());
+        let chana = &mock_server::recipes()[0];
+        assert_eq!(db.recipe(&chana.uid).unwrap().unwrap().name, chana.name);
+    }
+
+    #[test]
+    fn local_edits_are_tracked_until_synced() {
+        let db = RecipeDb::open_in_memory().unwrap();
+        let mut recipe = mock_server::recipes().remove(0);
+        db.save(&recipe, SyncState::Synced).unwrap();
+        assert!(db.unsynced().unwrap().is_empty());
+
+        recipe.notes = "Less salt".into();
+        recipe.hash = "edited".into();
+        db.save(&recipe, SyncState::Modified).unwrap();
+        assert_eq!(db.unsynced().unwrap(), vec![recipe.uid.clone()]);
+        assert_eq!(
+            db.status(&recipe.uid).unwrap(),
+            Some(("edited".to_string(), SyncState::Modified))
+        );
+
+        db.save(&recipe, SyncState::Synced).unwrap();
+        assert!(db.unsynced().unwrap().is_empty());
+    }
+
+    #[test]
+    fn edits_remember_the_copy_they_started_from() {
+        let db = RecipeDb::open_in_memory().unwrap();
+        let server = mock_server::recipes().remove(0);
+        db.save(&server, SyncState::Synced).unwrap();
+        assert!(db.base(&server.uid).unwrap().is_none());
+
+        let mut mine = server.clone();
+        mine.notes = "First edit".into();
+        mine.hash = "first".into();
+        db.save(&mine, SyncState::Modified).unwrap();
+        mine.notes = "Second edit".into();
+        mine.hash = "second".into();
+        db.save(&mine, SyncState::Modified).unwrap();
+        let notes = |recipe: Option<Recipe>| recipe.map(|recipe| recipe.notes);
+        assert_eq!(
+            notes(db.base(&server.uid).unwrap()),
+            Some(server.notes.clone())
+        );
+
+        let mut theirs = server.clone();
+        theirs.notes = "Phone edit".into();
+        theirs.hash = "phone".into();
+        db.mark_conflict(&server.uid, &theirs).unwrap();
+        assert_eq!(db.conflicted().unwrap(), vec![server.uid.clone()]);
+        assert!(db.pending(SystemTime::now()).unwrap().is_empty());
+        let (base, stored_mine, stored_theirs) = db.conflict(&server.uid).unwrap().unwrap();
+        assert_eq!(notes(base), Some(server.notes.clone()));
+        assert_eq!(stored_mine.notes, "Second edit");
+        assert_eq!(stored_theirs.notes, "Phone edit");
+
+        db.resolve(&mine, &theirs).unwrap();
+        assert!(db.conflicted().unwrap().is_empty());
+        assert_eq!(notes(db.base(&server.uid).unwrap()), Some(theirs.notes));
+        assert_eq!(db.pending(SystemTime::now()).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn failed_uploads_wait_before_retrying() {
+        let db = RecipeDb::open_in_memory().unwrap();
+        let mut recipes = mock_server::recipes();
+        let deleted = recipes.pop().unwrap();
+        let edited = recipes.pop().unwrap();
+        db.save(&deleted, SyncState::Synced).unwrap();
+        db.save(&edited, SyncState::Modified).unwrap();
+        db.delete_locally(&deleted.uid).unwrap();
+
+        let now = SystemTime::now();
+        assert_eq!(db.pending(now).unwrap().len(), 2);
+        assert!(db.recipes().unwrap().iter().all(|r| r.uid != deleted.uid));
+
+        let error = Error::Network("timed out".into());
+        db.mark_failed(&edited.uid, &error, |_| Duration::from_secs(60))
+            .unwrap();
+        assert_eq!(
+            db.pending(now).unwrap(),
+            vec![(deleted.uid.clone(), SyncState::Deleted)]
+        );
+        assert_eq!(db.pending(now + Duration::from_secs(120)).unwrap().len(), 2);
+
+        // a new edit is worth trying straight away
+        db.save(&edited, SyncState::Modified).unwrap();
+        assert_eq!(db.pending(now).unwrap().len(), 2);
+        assert_eq!(db.unsynced().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn old_json_cache_is_imported_once() {
+        let cache = tempfile::tempdir().unwrap();
+        for recipe in mock_server::recipes() {
+            fs::write(
+                cache.path().join(&recipe.uid),
+                serde_json::to_string(&recipe).unwrap(),
+            )
+            .unwrap();
+        }
+        fs::write(cache.path().join("categories.json"), "[]").unwrap();
+
+        let db = RecipeDb::open_in_memory().unwrap();
+        assert_eq!(
+            db.import_json_cache(cache.path()).unwrap(),
+            mock_server::recipes().len()
+        );
+        assert_eq!(db.import_json_cache(cache.path()).unwrap(), 0);
+
+        assert_eq!(db.recipes().unwrap().len(), mock_server::recipes().len());
+        assert!(cache.path().join("categories.json").exists());
+        assert!(!cache.path().join(&mock_server::recipes()[0].uid).exists());
+    }
+}
@@ -0,0 +1,568 @@
+use crate::app::category::{self, Category};
+use crate::app::theme::Theme;
+use crate::app::Message;
+
+use chrono::Utc;
+use iced::{
+    button, pane_grid, pick_list, scrollable, text_input, Align, Button, Column, Element, Length,
+    PickList, Row, Scrollable, Text, TextInput,
+};
+use paprika_api::api::Recipe;
+
+const LABEL_WIDTH: u16 = 100;
+const MAX_RATING: i32 = 5;
+
+/// Single-line recipe fields
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EditorField {
+    Name,
+    Servings,
+    PrepTime,
+    CookTime,
+    TotalTime,
+    Source,
+    SourceUrl,
+}
+
+/// Multi-line recipe fields, edited one line at a time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EditorSection {
+    Ingredients,
+    Directions,
+    Notes,
+}
+
+#[derive(Debug, Clone)]
+pub enum EditorMessage {
+    FieldChanged(EditorField, String),
+    LineChanged(EditorSection, usize, String),
+    /// Inserts an empty line after the given one
+    LineAdded(EditorSection, usize),
+    LineRemoved(EditorSection, usize),
+    /// Category uid
+    CategoryAdded(String),
+    CategoryRemoved(String),
+    RatingChanged(i32),
+    /// Backs out of discarding the changes
+    KeepEditing,
+}
+
+struct EditorLine {
+    text: String,
+    input: text_input::State,
+    remove: button::State,
+}
+
+impl EditorLine {
+    fn new(text: String) -> Self {
+        Self {
+            text,
+            input: text_input::State::new(),
+            remove: button::State::new(),
+        }
+    }
+
+    fn focused() -> Self {
+        Self {
+            text: String::new(),
+            input: text_input::State::focused(),
+            remove: button::State::new(),
+        }
+    }
+}
+
+struct LineList {
+    lines: Vec<EditorLine>,
+    add: button::State,
+}
+
+impl LineList {
+    fn new(text: &str) -> Self {
+        Self {
+            lines: text
+                .lines()
+                .map(|line| EditorLine::new(line.into()))
+                .collect(),
+            add: button::State::new(),
+        }
+    }
+
+    fn text(&self) -> String {
+        self.lines
+            .iter()
+            .map(|line| line.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn view(
+        &mut self,
+        pane: pane_grid::Pane,
+        section: EditorSection,
+        title: &str,
+        theme: Theme,
+    ) -> Element<'_, Message> {
+        let last = self.lines.len();
+        let mut column = Column::new().spacing(5).push(Text::new(title).size(20));
+
+        for (i, line) in self.lines.iter_mut().enumerate() {
+            let EditorLine {
+                text,
+                input,
+                remove,
+            } = line;
+
+            let row = Row::new()
+                .spacing(5)
+                .align_items(Align::Center)
+                .push(
+                    TextInput::new(input, "", text, move |value| {
+                        Message::Editor(pane, EditorMessage::LineChanged(section, i, value))
+                    })
+                    .style(theme)
+                    .on_submit(Message::Editor(pane, EditorMessage::LineAdded(section, i)))
+                    .padding(5),
+                )
+                .push(
+                    Button::new(remove, Text::new("Remove"))
+                        .style(theme)
+                        .on_press(Message::Editor(
+                            pane,
+                            EditorMessage::LineRemoved(section, i),
+                        )),
+                );
+
+            column = column.push(row);
+        }
+
+        column
+            .push(
+                Button::new(&mut self.add, Text::new("Add line"))
+                    .style(theme)
+                    .on_press(Message::Editor(
+                        pane,
+                        EditorMessage::LineAdded(section, last),
+                    )),
+            )
+            .into()
+    }
+}
+
+/// A form for creating or changing a recipe without leaving Ancho
+pub struct RecipeEditor {
+    /// The recipe as it was when editing started; fields the form doesn't cover pass through
+    recipe: Recipe,
+    name: String,
+    servings: String,
+    prep_time: String,
+    cook_time: String,
+    total_time: String,
+    source: String,
+    source_url: String,
+    ingredients: LineList,
+    directions: LineList,
+    notes: LineList,
+    /// Category uids
+    categories: Vec<String>,
+    available_categories: Vec<Category>,
+    rating: i32,
+    pub saving: bool,
+    /// The form as it first came out, to tell whether anything has been changed since
+    unedited: serde_json::Value,
+    /// Cancel or close was asked for while there were changes, so the form asks first
+    pub confirming_discard: bool,
+
+    scroll: scrollable::State,
+    inputs: [text_input::State; 7],
+    category_picker: pick_list::State<Category>,
+    category_buttons: Vec<button::State>,
+    rating_buttons: [button::State; MAX_RATING as usize + 1],
+    save: button::State,
+    cancel: button::State,
+    discard: button::State,
+}
+
+impl RecipeEditor {
+    /// `available_categories` are the categories that can be picked for the recipe
+    pub fn new(recipe: Recipe, available_categories: Vec<Category>) -> Self {
+        let mut editor = Self {
+            name: recipe.name.clone(),
+            servings: recipe.servings.clone(),
+            prep_time: recipe.prep_time.clone(),
+            cook_time: recipe.cook_time.clone(),
+            total_time: recipe.total_time.clone(),
+            source: recipe.source.clone(),
+            source_url: recipe.source_url.clone().unwrap_or_default(),
+            ingredients: LineList::new(&recipe.ingredients),
+            directions: LineList::new(&recipe.directions),
+            notes: LineList::new(&recipe.notes),
+            categories: recipe.categories.clone(),
+            available_categories,
+            rating: recipe.rating,
+            saving: false,
+            scroll: scrollable::State::new(),
+            inputs: Default::default(),
+            category_picker: pick_list::State::default(),
+            category_buttons: recipe
+                .categories
+                .iter()
+                .map(|_| button::State::new())
+                .collect(),
+            rating_buttons: Default::default(),
+            save: button::State::new(),
+            cancel: button::State::new(),
+            discard: button::State::new(),
+            unedited: serde_json::Value::Null,
+            confirming_discard: false,
+            recipe,
+        };
+        editor.unedited = editor.snapshot();
+        editor
+    }
+
+    fn snapshot(&self) -> serde_json::Value {
+        serde_json::to_value(self.to_recipe()).unwrap_or_default()
+    }
+
+    /// Whether closing the form would lose anything
+    pub fn has_changes(&self) -> bool {
+        self.snapshot() != self.unedited
+    }
+
+    /// A blank recipe, its servings filled in with `servings`
+    pub fn new_recipe(available_categories: Vec<Category>, servings: &str) -> Self {
+        let recipe = Recipe {
+            created: Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            servings: servings.into(),
+            ..Default::default()
+        };
+        Self::new(recipe, available_categories)
+    }
+
+    /// The uid of the recipe being edited; empty for a recipe that hasn't been saved yet
+    pub fn uid(&self) -> &str {
+        &self.recipe.uid
+    }
+
+    /// The recipe as it was when editing started
+    pub fn opened(&self) -> &Recipe {
+        &self.recipe
+    }
+
+    pub fn update(&mut self, message: EditorMessage) {
+        match message {
+            EditorMessage::FieldChanged(field, value) => *self.field_mut(field) = value,
+            EditorMessage::LineChanged(section, i, value) => {
+                if let Some(line) = self.section_mut(section).lines.get_mut(i) {
+                    line.text = value;
+                }
+            }
+            EditorMessage::LineAdded(section, i) => {
+                let lines = &mut self.section_mut(section).lines;
+                for line in lines.iter_mut() {
+                    line.input.unfocus();
+                }
+                let at = (i + 1).min(lines.len());
+                lines.insert(at, EditorLine::focused());
+            }
+            EditorMessage::LineRemoved(section, i) => {
+                let lines = &mut self.section_mut(section).lines;
+                if i < lines.len() {
+                    lines.remove(i);
+                }
+            }
+            EditorMessage::CategoryAdded(category) => {
+                if !self.categories.contains(&category) {
+                    self.categories.push(category);
+                    self.category_buttons.push(button::State::new());
+                }
+            }
+            EditorMessage::CategoryRemoved(category) => {
+                if let Some(i) = self.categories.iter().position(|c| *c == category) {
+                    self.categories.remove(i);
+                    self.category_buttons.remove(i);
+                }
+            }
+            EditorMessage::RatingChanged(rating) => self.rating = rating.clamp(0, MAX_RATING),
+            EditorMessage::KeepEditing => self.confirming_discard = false,
+        }
+    }
+
+    /// The recipe with the form's changes applied
+    pub fn to_recipe(&self) -> Recipe {
+        let source_url = if self.source_url.trim().is_empty() {
+            None
+        } else {
+            Some(self.source_url.trim().to_string())
+        };
+
+        Recipe {
+            name: self.name.trim().to_string(),
+            servings: self.servings.clone(),
+            prep_time: self.prep_time.clone(),
+            cook_time: self.cook_time.clone(),
+            total_time: self.total_time.clone(),
+            source: self.source.clone(),
+            source_url,
+            ingredients: self.ingredients.text(),
+            directions: self.directions.text(),
+            notes: self.notes.text(),
+            categories: self.categories.clone(),
+            rating: self.rating,
+            ..self.recipe.clone()
+        }
+    }
+
+    fn field_mut(&mut self, field: EditorField) -> &mut String {
+        match field {
+            EditorField::Name => &mut self.name,
+            EditorField::Servings => &mut self.servings,
+            EditorField::PrepTime => &mut self.prep_time,
+            EditorField::CookTime => &mut self.cook_time,
+            EditorField::TotalTime => &mut self.total_time,
+            EditorField::Source => &mut self.source,
+            EditorField::SourceUrl => &mut self.source_url,
+        }
+    }
+
+    fn section_mut(&mut self, section: EditorSection) -> &mut LineList {
+        match section {
+            EditorSection::Ingredients => &mut self.ingredients,
+            EditorSection::Directions => &mut self.directions,
+            EditorSection::Notes => &mut self.notes,
+        }
+    }
+
+    pub fn view(&mut self, pane: pane_grid::Pane, theme: Theme) -> Element<'_, Message> {
+        let title = if self.recipe.uid.is_empty() {
+            "New recipe"
+        } else {
+            "Edit recipe"
+        };
+        let unpicked: Vec<Category> = category::tree(&self.available_categories)
+            .into_iter()
+            .map(|(_, i)| &self.available_categories[i])
+            .filter(|category| !self.categories.contains(&category.uid))
+            .cloned()
+            .collect();
+
+        let mut content = Scrollable::new(&mut self.scroll)
+            .style(theme)
+            .width(Length::Fill)
+            .padding(10)
+            .spacing(10)
+            .push(Text::new(title).size(28));
+
+        let [name_input, servings_input, prep_input, cook_input, total_input, source_input, url_input] =
+            &mut self.inputs;
+        let fields = vec![
+            (name_input, "Name", EditorField::Name, &self.name),
+            (
+                servings_input,
+                "Servings",
+                EditorField::Servings,
+                &self.servings,
+            ),
+            (
+                prep_input,
+                "Prep time",
+                EditorField::PrepTime,
+                &self.prep_time,
+            ),
+            (
+                cook_input,
+                "Cook time",
+                EditorField::CookTime,
+                &self.cook_time,
+            ),
+            (
+                total_input,
+                "Total time",
+                EditorField::TotalTime,
+                &self.total_time,
+            ),
+            (source_input, "Source", EditorField::Source, &self.source),
+            (
+                url_input,
+                "Source URL",
+                EditorField::SourceUrl,
+                &self.source_url,
+            ),
+        ];
+        for (state, label, field, value) in fields {
+            content = content.push(
+                Row::new()
+                    .spacing(5)
+                    .align_items(Align::Center)
+                    .push(Text::new(label).width(Length::Units(LABEL_WIDTH)))
+                    .push(
+                        TextInput::new(state, label, value, move |value| {
+                            Message::Editor(pane, EditorMessage::FieldChanged(field, value))
+                        })
+                        .style(theme)
+                        .padding(5),
+                    ),
+            );
+        }
+
+        content = content
+            .push(
+                self.ingredients
+                    .view(pane, EditorSection::Ingredients, "Ingredients", theme),
+            )
+            .push(
+                self.directions
+                    .view(pane, EditorSection::Directions, "Directions", theme),
+            )
+            .push(self.notes.view(pane, EditorSection::Notes, "Notes", theme));
+
+        let mut categories = Row::new()
+            .spacing(5)
+            .align_items(Align::Center)
+            .push(Text::new("Categories").width(Length::Units(LABEL_WIDTH)));
+        for (uid, state) in self.categories.iter().zip(self.category_buttons.iter_mut()) {
+            let name = category::name_of(&self.available_categories, uid);
+            categories = categories.push(
+                Button::new(state, Text::new(format!("{} ✕", name)))
+                    .style(theme)
+                    .on_press(Message::Editor(
+                        pane,
+                        EditorMessage::CategoryRemoved(uid.clone()),
+                    )),
+            );
+        }
+        if !unpicked.is_empty() {
+            categories = categories.push(
+                PickList::new(&mut self.category_picker, unpicked, None, move |category| {
+                    Message::Editor(pane, EditorMessage::CategoryAdded(category.uid))
+                })
+                .style(theme),
+            );
+        }
+        content = content.push(categories);
+
+        let mut rating = Row::new()
+            .spacing(5)
+            .align_items(Align::Center)
+            .push(Text::new("Rating").width(Length::Units(LABEL_WIDTH)));
+        for (stars, state) in self.rating_buttons.iter_mut().enumerate() {
+            let stars = stars as i32;
+            let label = match stars {
+                0 => "None".to_string(),
+                _ if stars <= self.rating => "★".to_string(),
+                _ => "☆".to_string(),
+            };
+            rating = rating.push(
+                Button::new(state, Text::new(label))
+                    .style(theme)
+                    .on_press(Message::Editor(pane, EditorMessage::RatingChanged(stars))),
+            );
+        }
+        content = content.push(rating);
+
+        if self.confirming_discard {
+            content = content.push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Align::Center)
+                    .push(Text::new("Discard your changes?"))
+                    .push(
+                        Button::new(&mut self.discard, Text::new("Discard"))
+                            .style(theme)
+                            .on_press(Message::EditorDiscarded(pane)),
+                    )
+                    .push(
+                        Button::new(&mut self.cancel, Text::new("Keep editing"))
+                            .style(theme)
+                            .on_press(Message::Editor(pane, EditorMessage::KeepEditing)),
+                    ),
+            );
+            return content.into();
+        }
+
+        let mut save = Button::new(
+            &mut self.save,
+            Text::new(if self.saving { "Saving…" } else { "Save" }),
+        )
+        .style(theme);
+        if !self.saving {
+            save = save.on_press(Message::EditorSaved(pane));
+        }
+        content = content.push(
+            Row::new().spacing(10).push(save).push(
+                Button::new(&mut self.cancel, Text::new("Cancel"))
+                    .style(theme)
+                    .on_press(Message::EditorCancelled(pane)),
+            ),
+        );
+
+        content.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chana_masala() -> Recipe {
+        Recipe {
+            uid: "0C1A4B3E".into(),
+            name: "Chana Masala".into(),
+            ingredients: "2 tbsp oil\n1 onion".into(),
+            directions: "Fry the onion.".into(),
+            description: "Weeknight staple".into(),
+            categories: vec!["Indian".into()],
+            rating: 4,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn untouched_editor_returns_original_recipe() {
+        let recipe = chana_masala();
+        let editor = RecipeEditor::new(recipe.clone(), Vec::new());
+
+        let edited = editor.to_recipe();
+
+        assert!(!editor.has_changes());
+        assert_eq!(edited.ingredients, recipe.ingredients);
+        assert_eq!(edited.directions, recipe.directions);
+        assert_eq!(edited.description, recipe.description);
+        assert_eq!(edited.categories, recipe.categories);
+        assert_eq!(edited.rating, recipe.rating);
+    }
+
+    #[test]
+    fn edits_are_applied_to_recipe() {
+        let vegan = Category {
+            uid: "Vegan".into(),
+            name: "Vegan".into(),
+            ..Default::default()
+        };
+        let mut editor = RecipeEditor::new(chana_masala(), vec![vegan]);
+
+        editor.update(EditorMessage::FieldChanged(
+            EditorField::Name,
+            " Chana Masala (quick) ".into(),
+        ));
+        editor.update(EditorMessage::LineAdded(EditorSection::Ingredients, 0));
+        editor.update(EditorMessage::LineChanged(
+            EditorSection::Ingredients,
+            1,
+            "3 cloves garlic".into(),
+        ));
+        editor.update(EditorMessage::LineRemoved(EditorSection::Ingredients, 2));
+        editor.update(EditorMessage::CategoryAdded("Vegan".into()));
+        editor.update(EditorMessage::CategoryRemoved("Indian".into()));
+        editor.update(EditorMessage::RatingChanged(9));
+
+        let edited = editor.to_recipe();
+        assert!(editor.has_changes());
+
+        assert_eq!(edited.uid, "0C1A4B3E");
+        assert_eq!(edited.name, "Chana Masala (quick)");
+        assert_eq!(edited.ingredients, "2 tbsp oil\n3 cloves garlic");
+        assert_eq!(edited.categories, vec!["Vegan".to_string()]);
+        assert_eq!(edited.rating, MAX_RATING);
+    }
+}
@@ -0,0 +1,355 @@
+use crate::app::category::{self, Category};
+use crate::app::theme::Theme;
+use crate::app::Message;
+
+use iced::{
+    button, pane_grid, pick_list, text_input, Align, Button, Checkbox, Column, Element, Length,
+    PickList, Row, Text, TextInput,
+};
+use paprika_api::api::Recipe;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+const MAX_RATING: i32 = 5;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CategoryChoice {
+    All,
+    /// A category and how deeply it's nested, for indenting
+    Only(Category, usize),
+}
+
+impl fmt::Display for CategoryChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CategoryChoice::All => write!(f, "All categories"),
+            CategoryChoice::Only(category, depth) => {
+                write!(f, "{}{}", "  ".repeat(*depth), category.name)
+            }
+        }
+    }
+}
+
+/// The lowest rating a recipe needs to be shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RatingChoice(pub i32);
+
+impl fmt::Display for RatingChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            0 => write!(f, "Any rating"),
+            MAX_RATING => write!(f, "{}", "★".repeat(MAX_RATING as usize)),
+            stars => write!(f, "{} and up", "★".repeat(stars as usize)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum FilterMessage {
+    QueryChanged(String),
+    CategoryChanged(CategoryChoice),
+    MinRatingChanged(RatingChoice),
+    FavoritesToggled(bool),
+    PinnedToggled(bool),
+    Cleared,
+}
+
+/// Narrows the recipe list down by search text, category, rating, favorites and pins
+#[derive(Default)]
+pub struct RecipeFilter {
+    query: String,
+    /// `query`, trimmed and folded once rather than for every recipe
+    folded_query: String,
+    category: Option<String>,
+    min_rating: i32,
+    favorites_only: bool,
+    pinned_only: bool,
+
+    search: text_input::State,
+    category_picker: pick_list::State<CategoryChoice>,
+    rating_picker: pick_list::State<RatingChoice>,
+    clear: button::State,
+}
+
+impl RecipeFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Puts the cursor at the end of the search box, or takes it out
+    pub fn focus_search(&mut self, focused: bool) {
+        if focused {
+            self.search.focus();
+            self.search.move_cursor_to_end();
+        } else {
+            self.search.unfocus();
+        }
+    }
+
+    pub fn update(&mut self, message: FilterMessage) {
+        match message {
+            FilterMessage::QueryChanged(query) => {
+                self.folded_query = fold(query.trim());
+                self.query = query;
+            }
+            FilterMessage::CategoryChanged(CategoryChoice::All) => self.category = None,
+            FilterMessage::CategoryChanged(CategoryChoice::Only(category, _)) => {
+                self.category = Some(category.uid)
+            }
+            FilterMessage::MinRatingChanged(RatingChoice(rating)) => {
+                self.min_rating = rating.clamp(0, MAX_RATING)
+            }
+            FilterMessage::FavoritesToggled(on) => self.favorites_only = on,
+            FilterMessage::PinnedToggled(on) => self.pinned_only = on,
+            FilterMessage::Cleared => {
+                self.query.clear();
+                self.folded_query.clear();
+                self.category = None;
+                self.min_rating = 0;
+                self.favorites_only = false;
+                self.pinned_only = false;
+            }
+        }
+    }
+
+    /// Whether `recipe` should be listed. Search text is matched ignoring case and accents
+    /// against the name, ingredients, notes and source, as folded by `index`.
+    pub fn matches(&self, recipe: &Recipe, index: &SearchIndex) -> bool {
+        if recipe.rating < self.min_rating
+            || (self.favorites_only && !recipe.on_favorites)
+            || (self.pinned_only && !recipe.is_pinned)
+        {
+            return false;
+        }
+
+        if let Some(category) = &self.category {
+            if !recipe.categories.contains(category) {
+                return false;
+            }
+        }
+
+        if self.folded_query.is_empty() {
+            return true;
+        }
+        match index.texts.get(&recipe.uid) {
+            Some((_, text)) => text.contains(&self.folded_query),
+            None => searchable_text(recipe).contains(&self.folded_query),
+        }
+    }
+
+    /// `categories` are offered in the category picker
+    pub fn view(
+        &mut self,
+        pane: pane_grid::Pane,
+        categories: &[Category],
+        theme: Theme,
+    ) -> Element<'_, Message> {
+        let category_choices: Vec<CategoryChoice> = std::iter::once(CategoryChoice::All)
+            .chain(
+                category::tree(categories)
+                    .into_iter()
+                    .map(|(depth, i)| CategoryChoice::Only(categories[i].clone(), depth)),
+            )
+            .collect();
+        let selected_category = category_choices
+            .iter()
+            .find(|choice| match (choice, &self.category) {
+                (CategoryChoice::Only(category, _), Some(uid)) => &category.uid == uid,
+                (CategoryChoice::All, None) => true,
+                _ => false,
+            })
+            .cloned();
+        let rating_choices: Vec<RatingChoice> = (0..=MAX_RATING).map(RatingChoice).collect();
+
+        let search = TextInput::new(
+            &mut self.search,
+            "Search recipes",
+            &self.query,
+            move |query| Message::Filter(pane, FilterMessage::QueryChanged(query)),
+        )
+        .style(theme)
+        .padding(5)
+        .width(Length::Fill);
+
+        let filters = Row::new()
+            .spacing(10)
+            .align_items(Align::Center)
+            .push(
+                PickList::new(
+                    &mut self.category_picker,
+                    category_choices,
+                    selected_category,
+                    move |choice| Message::Filter(pane, FilterMessage::CategoryChanged(choice)),
+                )
+                .style(theme),
+            )
+            .push(
+                PickList::new(
+                    &mut self.rating_picker,
+                    rating_choices,
+                    Some(RatingChoice(self.min_rating)),
+                    move |choice| Message::Filter(pane, FilterMessage::MinRatingChanged(choice)),
+                )
+                .style(theme),
+            )
+            .push(
+                Checkbox::new(self.favorites_only, "Favorites", move |on| {
+                    Message::Filter(pane, FilterMessage::FavoritesToggled(on))
+                })
+                .style(theme),
+            )
+            .push(
+                Checkbox::new(self.pinned_only, "Pinned", move |on| {
+                    Message::Filter(pane, FilterMessage::PinnedToggled(on))
+                })
+                .style(theme),
+            )
+            .push(
+                Button::new(&mut self.clear, Text::new("Clear"))
+                    .style(theme)
+                    .on_press(Message::Filter(pane, FilterMessage::Cleared)),
+            );
+
+        Column::new().spacing(5).push(search).push(filters).into()
+    }
+}
+
+/// Every recipe's searchable text, folded ahead of time so typing a search doesn't fold all of
+/// it again on every keystroke
+#[derive(Debug, Default)]
+pub struct SearchIndex {
+    /// By uid: the recipe hash the text was folded at, and the text
+    texts: HashMap<String, (String, String)>,
+}
+
+impl SearchIndex {
+    /// Folds the text of recipes that are new or have changed since, going by their hash, and
+    /// forgets recipes that are gone
+    pub fn update(&mut self, recipes: &[Recipe]) {
+        for recipe in recipes {
+            let fresh = matches!(
+                self.texts.get(&recipe.uid),
+                Some((hash, _)) if !hash.is_empty() && *hash == recipe.hash
+            );
+            if !fresh {
+                self.texts.insert(
+                    recipe.uid.clone(),
+                    (recipe.hash.clone(), searchable_text(recipe)),
+                );
+            }
+        }
+        if self.texts.len() > recipes.len() {
+            let uids: HashSet<&str> = recipes.iter().map(|recipe| recipe.uid.as_str()).collect();
+            self.texts.retain(|uid, _| uids.contains(uid.as_str()));
+        }
+    }
+}
+
+/// The fields search looks in, folded, one per line so a match can't span two of them
+fn searchable_text(recipe: &Recipe) -> String {
+    [
+        &recipe.name,
+        &recipe.ingredients,
+        &recipe.notes,
+        &recipe.source,
+    ]
+    .iter()
+    .map(|field| fold(field))
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+/// Lowercases `text` and strips its accents so "Crème" and "creme" compare equal
+pub fn fold(text: &str) -> String {
+    text.nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::backend::mock_server;
+
+    fn names(filter: &RecipeFilter) -> Vec<String> {
+        let recipes = mock_server::recipes();
+        let mut index = SearchIndex::default();
+        index.update(&recipes);
+        recipes
+            .into_iter()
+            .filter(|recipe| filter.matches(recipe, &index))
+            .map(|recipe| recipe.name)
+            .collect()
+    }
+
+    #[test]
+    fn search_ignores_case_and_accents() {
+        let mut filter = RecipeFilter::new();
+        filter.update(FilterMessage::QueryChanged("CREME brulee".into()));
+
+        assert_eq!(names(&filter), vec!["Crème Brûlée".to_string()]);
+    }
+
+    #[test]
+    fn search_looks_inside_ingredients() {
+        let mut filter = RecipeFilter::new();
+        filter.update(FilterMessage::QueryChanged("chickpea".into()));
+
+        assert_eq!(names(&filter), vec!["Chana Masala".to_string()]);
+    }
+
+    #[test]
+    fn index_refolds_only_changed_recipes() {
+        let mut recipes = mock_server::recipes();
+        let mut index = SearchIndex::default();
+        let mut filter = RecipeFilter::new();
+        filter.update(FilterMessage::QueryChanged("tofu".into()));
+
+        recipes[0].hash = "before".into();
+        index.update(&recipes);
+        assert!(!filter.matches(&recipes[0], &index));
+
+        // an edit changes the hash, which is what tells the index to fold the text again
+        recipes[0].notes = "Use tofu instead".into();
+        index.update(&recipes);
+        assert!(!filter.matches(&recipes[0], &index));
+        recipes[0].hash = "after".into();
+        index.update(&recipes);
+        assert!(filter.matches(&recipes[0], &index));
+
+        let gone = recipes.remove(0);
+        index.update(&recipes);
+        assert!(!index.texts.contains_key(&gone.uid));
+    }
+
+    #[test]
+    fn category_filter_matches_by_uid() {
+        let categories = mock_server::categories();
+        let dessert = categories
+            .into_iter()
+            .find(|category| category.name == "Dessert")
+            .unwrap();
+
+        let mut filter = RecipeFilter::new();
+        filter.update(FilterMessage::CategoryChanged(CategoryChoice::Only(
+            dessert, 0,
+        )));
+
+        assert_eq!(names(&filter), vec!["Crème Brûlée".to_string()]);
+    }
+
+    #[test]
+    fn flags_and_rating_narrow_results_until_cleared() {
+        let mut filter = RecipeFilter::new();
+        filter.update(FilterMessage::MinRatingChanged(RatingChoice(4)));
+        assert_eq!(names(&filter).len(), 2);
+
+        filter.update(FilterMessage::FavoritesToggled(true));
+        assert_eq!(names(&filter), vec!["Chana Masala".to_string()]);
+
+        filter.update(FilterMessage::Cleared);
+        assert_eq!(names(&filter).len(), mock_server::recipes().len());
+    }
+}
@@ -0,0 +1,424 @@
+use crate::app::theme::Theme;
+use crate::app::Message;
+
+use iced::{
+    button, pane_grid, scrollable, Align, Button, Column, Element, Length, Row, Scrollable, Text,
+};
+use paprika_api::api::Recipe;
+
+const LABEL_WIDTH: u16 = 100;
+
+/// The recipe fields a merge goes through one by one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeField {
+    Name,
+    Description,
+    Ingredients,
+    Directions,
+    Notes,
+    NutritionalInfo,
+    Servings,
+    Difficulty,
+    PrepTime,
+    CookTime,
+    TotalTime,
+    Source,
+    SourceUrl,
+}
+
+impl MergeField {
+    pub const ALL: [MergeField; 13] = [
+        MergeField::Name,
+        MergeField::Description,
+        MergeField::Ingredients,
+        MergeField::Directions,
+        MergeField::Notes,
+        MergeField::NutritionalInfo,
+        MergeField::Servings,
+        MergeField::Difficulty,
+        MergeField::PrepTime,
+        MergeField::CookTime,
+        MergeField::TotalTime,
+        MergeField::Source,
+        MergeField::SourceUrl,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            MergeField::Name => "Name",
+            MergeField::Description => "Description",
+            MergeField::Ingredients => "Ingredients",
+            MergeField::Directions => "Directions",
+            MergeField::Notes => "Notes",
+            MergeField::NutritionalInfo => "Nutrition",
+            MergeField::Servings => "Servings",
+            MergeField::Difficulty => "Difficulty",
+            MergeField::PrepTime => "Prep time",
+            MergeField::CookTime => "Cook time",
+            MergeField::TotalTime => "Total time",
+            MergeField::Source => "Source",
+            MergeField::SourceUrl => "Source URL",
+        }
+    }
+
+    fn get(self, recipe: &Recipe) -> &str {
+        match self {
+            MergeField::Name => &recipe.name,
+            MergeField::Description => &recipe.description,
+            MergeField::Ingredients => &recipe.ingredients,
+            MergeField::Directions => &recipe.directions,
+            MergeField::Notes => &recipe.notes,
+            MergeField::NutritionalInfo => &recipe.nutritional_info,
+            MergeField::Servings => &recipe.servings,
+            MergeField::Difficulty => &recipe.difficulty,
+            MergeField::PrepTime => &recipe.prep_time,
+            MergeField::CookTime => &recipe.cook_time,
+            MergeField::TotalTime => &recipe.total_time,
+            MergeField::Source => &recipe.source,
+            MergeField::SourceUrl => recipe.source_url.as_deref().unwrap_or(""),
+        }
+    }
+
+    fn set(self, recipe: &mut Recipe, value: String) {
+        match self {
+            MergeField::Name => recipe.name = value,
+            MergeField::Description => recipe.description = value,
+            MergeField::Ingredients => recipe.ingredients = value,
+            MergeField::Directions => recipe.directions = value,
+            MergeField::Notes => recipe.notes = value,
+            MergeField::NutritionalInfo => recipe.nutritional_info = value,
+            MergeField::Servings => recipe.servings = value,
+            MergeField::Difficulty => recipe.difficulty = value,
+            MergeField::PrepTime => recipe.prep_time = value,
+            MergeField::CookTime => recipe.cook_time = value,
+            MergeField::TotalTime => recipe.total_time = value,
+            MergeField::Source => recipe.source = value,
+            MergeField::SourceUrl => recipe.source_url = Some(value).filter(|url| !url.is_empty()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeChoice {
+    Mine,
+    Theirs,
+    Combine,
+}
+
+#[derive(Debug, Clone)]
+pub enum MergeMessage {
+    Chose(MergeField, MergeChoice),
+}
+
+/// Merges two edits of some text line by line: lines either side removed stay removed, and
+/// lines only they added go after ours
+pub fn combine(base: &str, mine: &str, theirs: &str) -> String {
+    if mine == base || mine == theirs {
+        return theirs.into();
+    }
+    if theirs == base {
+        return mine.into();
+    }
+
+    let mut lines: Vec<&str> = mine.lines().collect();
+    for line in theirs.lines() {
+        if !lines.contains(&line) && !base.lines().any(|old| old == line) {
+            lines.push(line);
+        }
+    }
+    lines.join("\n")
+}
+
+struct MergeRow {
+    field: MergeField,
+    choice: MergeChoice,
+    /// What the merge picked before anyone chose
+    suggested: MergeChoice,
+    mine: button::State,
+    theirs: button::State,
+    combine: button::State,
+}
+
+/// Walks through every field the local and server copies of a recipe disagree on
+pub struct RecipeMerge {
+    base: Option<Recipe>,
+    mine: Recipe,
+    pub theirs: Recipe,
+    rows: Vec<MergeRow>,
+    pub saving: bool,
+
+    scroll: scrollable::State,
+    save: button::State,
+    cancel: button::State,
+}
+
+impl RecipeMerge {
+    /// Each field starts out with whatever a three-way merge would pick: the side that changed
+    /// it, or both combined when both did
+    pub fn new(base: Option<Recipe>, mine: Recipe, theirs: Recipe) -> Self {
+        let rows = MergeField::ALL
+            .iter()
+            .filter(|field| field.get(&mine) != field.get(&theirs))
+            .map(|&field| {
+                let choice = match &base {
+                    Some(base) if field.get(base) == field.get(&mine) => MergeChoice::Theirs,
+                    Some(base) if field.get(base) == field.get(&theirs) => MergeChoice::Mine,
+                    _ => MergeChoice::Combine,
+                };
+                MergeRow {
+                    field,
+                    choice,
+                    suggested: choice,
+                    mine: button::State::new(),
+                    theirs: button::State::new(),
+                    combine: button::State::new(),
+                }
+            })
+            .collect();
+
+        Self {
+            base,
+            mine,
+            theirs,
+            rows,
+            saving: false,
+            scroll: scrollable::State::new(),
+            save: button::State::new(),
+            cancel: button::State::new(),
+        }
+    }
+
+    pub fn uid(&self) -> &str {
+        &self.mine.uid
+    }
+
+    /// Whether any field has been chosen differently from what the merge suggested
+    pub fn has_changes(&self) -> bool {
+        self.rows.iter().any(|row| row.choice != row.suggested)
+    }
+
+    pub fn update(&mut self, message: MergeMessage) {
+        match message {
+            MergeMessage::Chose(field, choice) => {
+                if let Some(row) = self.rows.iter_mut().find(|row| row.field == field) {
+                    row.choice = choice;
+                }
+            }
+        }
+    }
+
+    fn merged_field(&self, field: MergeField, choice: MergeChoice) -> String {
+        match choice {
+            MergeChoice::Mine => field.get(&self.mine).into(),
+            MergeChoice::Theirs => field.get(&self.theirs).into(),
+            MergeChoice::Combine => combine(
+                self.base.as_ref().map_or("", |base| field.get(base)),
+                field.get(&self.mine),
+                field.get(&self.theirs),
+            ),
+        }
+    }
+
+    /// The server copy with the chosen text, and with our changes to everything else the merge
+    /// doesn't ask about (rating, categories and flags)
+    pub fn to_recipe(&self) -> Recipe {
+        let mut merged = self.theirs.clone();
+
+        // without a base, all we know is that these are our values
+        let we_changed = |differs: fn(&Recipe, &Recipe) -> bool| match &self.base {
+            Some(base) => differs(base, &self.mine),
+            None => true,
+        };
+        if we_changed(|a, b| a.rating != b.rating) {
+            merged.rating = self.mine.rating;
+        }
+        if we_changed(|a, b| a.categories != b.categories) {
+            merged.categories = self.mine.categories.clone();
+        }
+        if we_changed(|a, b| a.is_pinned != b.is_pinned) {
+            merged.is_pinned = self.mine.is_pinned;
+        }
+        if we_changed(|a, b| a.on_favorites != b.on_favorites) {
+            merged.on_favorites = self.mine.on_favorites;
+        }
+        if we_changed(|a, b| a.on_grocery_list != b.on_grocery_list) {
+            merged.on_grocery_list = self.mine.on_grocery_list;
+        }
+        if we_changed(|a, b| a.in_trash != b.in_trash) {
+            merged.in_trash = self.mine.in_trash;
+        }
+
+        for row in &self.rows {
+            row.field
+                .set(&mut merged, self.merged_field(row.field, row.choice));
+        }
+        merged
+    }
+
+    pub fn view(&mut self, pane: pane_grid::Pane, theme: Theme) -> Element<'_, Message> {
+        let previews: Vec<String> = self
+            .rows
+            .iter()
+            .map(|row| self.merged_field(row.field, MergeChoice::Combine))
+            .collect();
+        let RecipeMerge {
+            mine,
+            theirs,
+            rows,
+            saving,
+            scroll,
+            save,
+            cancel,
+            ..
+        } = self;
+
+        let mut content = Scrollable::new(scroll)
+            .style(theme)
+            .width(Length::Fill)
+            .padding(10)
+            .spacing(15)
+            .push(Text::new(format!("Merge \"{}\"", mine.name)).size(28))
+            .push(Text::new(
+                "This recipe was changed on another device while you edited it here. \
+                 Pick what to keep for each field that differs.",
+            ));
+
+        if rows.is_empty() {
+            content = content.push(Text::new("Both copies have the same text."));
+        }
+
+        let marker = |selected: bool| if selected { "• " } else { "" };
+        for (row, combined) in rows.iter_mut().zip(previews) {
+            let MergeRow {
+                field,
+                choice,
+                mine: mine_button,
+                theirs: theirs_button,
+                combine: combine_button,
+                ..
+            } = row;
+            let field = *field;
+            let choice = *choice;
+
+            let versions = Row::new()
+                .spacing(10)
+                .push(
+                    Column::new()
+                        .width(Length::FillPortion(1))
+                        .push(Text::new("Mine").size(16))
+                        .push(Text::new(field.get(mine))),
+                )
+                .push(
+                    Column::new()
+                        .width(Length::FillPortion(1))
+                        .push(Text::new("Theirs").size(16))
+                        .push(Text::new(field.get(theirs))),
+                );
+
+            let choose = |state, label: &str, option| {
+                Button::new(
+                    state,
+                    Text::new(format!("{}{}", marker(choice == option), label)),
+                )
+                .style(theme)
+                .on_press(Message::Merge(pane, MergeMessage::Chose(field, option)))
+            };
+            let buttons = Row::new()
+                .spacing(10)
+                .align_items(Align::Center)
+                .push(Text::new(field.label()).width(Length::Units(LABEL_WIDTH)))
+                .push(choose(mine_button, "Keep mine", MergeChoice::Mine))
+                .push(choose(theirs_button, "Keep theirs", MergeChoice::Theirs))
+                .push(choose(combine_button, "Combine", MergeChoice::Combine));
+
+            let mut section = Column::new().spacing(5).push(buttons).push(versions);
+            if choice == MergeChoice::Combine {
+                section = section
+                    .push(Text::new("Combined").size(16))
+                    .push(Text::new(combined));
+            }
+            content = content.push(section);
+        }
+
+        let mut save_button = Button::new(
+            save,
+            Text::new(if *saving { "Saving…" } else { "Save merge" }),
+        )
+        .style(theme);
+        if !*saving {
+            save_button = save_button.on_press(Message::MergeSaved(pane));
+        }
+        content = content.push(
+            Row::new().spacing(10).push(save_button).push(
+                Button::new(cancel, Text::new("Later"))
+                    .style(theme)
+                    .on_press(Message::Close(pane)),
+            ),
+        );
+
+        content.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recipe(notes: &str, servings: &str) -> Recipe {
+        Recipe {
+            uid: "CHANA".into(),
+            name: "Chana Masala".into(),
+            notes: notes.into(),
+            servings: servings.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn combine_keeps_both_sides_additions() {
+        let base = "Soak overnight\nUse fresh ginger";
+        let mine = "Soak overnight\nUse fresh ginger\nLess salt";
+        let theirs = "Use fresh ginger\nMore chilli";
+
+        assert_eq!(
+            combine(base, mine, theirs),
+            "Soak overnight\nUse fresh ginger\nLess salt\nMore chilli"
+        );
+        assert_eq!(combine(base, base, theirs), theirs);
+        assert_eq!(combine(base, mine, base), mine);
+    }
+
+    #[test]
+    fn fields_changed_on_one_side_merge_themselves() {
+        let base = recipe("Use fresh ginger", "4");
+        let mut mine = recipe("Use fresh ginger\nLess salt", "4");
+        mine.rating = 5;
+        mine.in_trash = true;
+        let theirs = recipe("Use fresh ginger\nMore chilli", "6");
+
+        let mut merge = RecipeMerge::new(Some(base), mine, theirs);
+        let choices: Vec<(MergeField, MergeChoice)> = merge
+            .rows
+            .iter()
+            .map(|row| (row.field, row.choice))
+            .collect();
+        assert_eq!(
+            choices,
+            vec![
+                (MergeField::Notes, MergeChoice::Combine),
+                (MergeField::Servings, MergeChoice::Theirs),
+            ]
+        );
+
+        let merged = merge.to_recipe();
+        assert_eq!(merged.notes, "Use fresh ginger\nLess salt\nMore chilli");
+        assert_eq!(merged.servings, "6");
+        assert_eq!(merged.rating, 5);
+        assert!(merged.in_trash);
+        assert!(!merge.has_changes());
+
+        merge.update(MergeMessage::Chose(MergeField::Notes, MergeChoice::Mine));
+        assert_eq!(merge.to_recipe().notes, "Use fresh ginger\nLess salt");
+        assert!(merge.has_changes());
+    }
+}
@@ -0,0 +1,213 @@
+use paprika_api::api::Recipe;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::time::SystemTime;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortKey {
+    Name,
+    Created,
+    Rating,
+    TotalTime,
+    RecentlyEdited,
+}
+
+impl SortKey {
+    pub const ALL: [SortKey; 5] = [
+        SortKey::Name,
+        SortKey::Created,
+        SortKey::Rating,
+        SortKey::TotalTime,
+        SortKey::RecentlyEdited,
+    ];
+}
+
+impl fmt::Display for SortKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            SortKey::Name => "Name",
+            SortKey::Created => "Date added",
+            SortKey::Rating => "Rating",
+            SortKey::TotalTime => "Total time",
+            SortKey::RecentlyEdited => "Recently edited",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// How the recipe list is ordered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SortOrder {
+    pub key: SortKey,
+    pub descending: bool,
+}
+
+impl Default for SortOrder {
+    fn default() -> Self {
+        Self {
+            key: SortKey::Name,
+            descending: false,
+        }
+    }
+}
+
+impl SortOrder {
+    /// Sorts `recipes` in place. `edited` holds when each recipe was last seen to change; recipes
+    /// missing from it sort as the oldest. Ties are broken by name so the order never reshuffles.
+    pub fn sort(&self, recipes: &mut [Recipe], edited: &HashMap<String, SystemTime>) {
+        let directed = |ordering: Ordering| {
+            if self.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        };
+        let by_name = |a: &Recipe, b: &Recipe| a.name.to_lowercase().cmp(&b.name.to_lowercase());
+
+        recipes.sort_by(|a, b| {
+            let ordering = match self.key {
+                SortKey::Name => directed(by_name(a, b)),
+                SortKey::Created => directed(a.created.cmp(&b.created)),
+                SortKey::Rating => directed(a.rating.cmp(&b.rating)),
+                // recipes without a readable time go last either way
+                SortKey::TotalTime => match (minutes(&a.total_time), minutes(&b.total_time)) {
+                    (Some(a), Some(b)) => directed(a.cmp(&b)),
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                },
+                SortKey::RecentlyEdited => directed(edited.get(&a.uid).cmp(&edited.get(&b.uid))),
+            };
+
+            ordering.then_with(|| by_name(a, b))
+        });
+    }
+}
+
+/// Reads free-form times like "1 hr 30 mins", "45 minutes", "2h" or "1 day" as a number of
+/// minutes. Numbers followed by a unit that isn't a time, like "2 servings", are skipped, and
+/// absurdly long times stop at `u32::MAX` rather than overflowing.
+fn minutes(time: &str) -> Option<u32> {
+    let time = time.to_lowercase();
+    let mut total: Option<u32> = None;
+    let mut number: Option<u32> = None;
+    let mut chars = time.chars().peekable();
+    let add = |total: &mut Option<u32>, n: u32| {
+        *total = Some(total.unwrap_or(0).saturating_add(n));
+    };
+
+    while let Some(&c) = chars.peek() {
+        if c.is_ascii_digit() {
+            let mut digits = String::new();
+            while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit()) {
+                digits.push(c);
+                chars.next();
+            }
+            // a bare number with no unit is taken as minutes
+            if let Some(n) = number.take() {
+                add(&mut total, n);
+            }
+            number = Some(digits.parse().unwrap_or(u32::MAX));
+        } else if c.is_alphabetic() {
+            let mut unit = String::new();
+            while let Some(&c) = chars.peek().filter(|c| c.is_alphabetic()) {
+                unit.push(c);
+                chars.next();
+            }
+            if let Some(n) = number.take() {
+                if let Some(factor) = unit_minutes(&unit) {
+                    add(&mut total, n.saturating_mul(factor));
+                }
+            }
+        } else {
+            chars.next();
+        }
+    }
+
+    if let Some(n) = number {
+        add(&mut total, n);
+    }
+    total
+}
+
+/// How many minutes one of `unit` is, if it's a unit of time
+fn unit_minutes(unit: &str) -> Option<u32> {
+    match unit {
+        "m" | "min" | "mins" | "minute" | "minutes" => Some(1),
+        "h" | "hr" | "hrs" | "hour" | "hours" => Some(60),
+        "d" | "day" | "days" => Some(24 * 60),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::backend::mock_server;
+    use std::time::Duration;
+
+    fn sorted_names(order: SortOrder, edited: &HashMap<String, SystemTime>) -> Vec<String> {
+        let mut recipes = mock_server::recipes();
+        order.sort(&mut recipes, edited);
+        recipes.into_iter().map(|recipe| recipe.name).collect()
+    }
+
+    #[test]
+    fn reads_common_time_formats() {
+        assert_eq!(minutes("40 mins"), Some(40));
+        assert_eq!(minutes("4 hrs"), Some(240));
+        assert_eq!(minutes("1 hr 30 minutes"), Some(90));
+        assert_eq!(minutes("2h15m"), Some(135));
+        assert_eq!(minutes("25"), Some(25));
+        assert_eq!(minutes("overnight"), None);
+        assert_eq!(minutes("2 days"), Some(2 * 24 * 60));
+        assert_eq!(minutes("20 mins plus 2 servings"), Some(20));
+        assert_eq!(minutes("4294967295 hours 5 mins"), Some(u32::MAX));
+        assert_eq!(minutes("99999999999"), Some(u32::MAX));
+    }
+
+    #[test]
+    fn newest_first_puts_latest_import_on_top() {
+        let order = SortOrder {
+            key: SortKey::Created,
+            descending: true,
+        };
+
+        assert_eq!(
+            sorted_names(order, &HashMap::new()),
+            vec!["Chana Masala", "Crème Brûlée", "Old Pancakes"]
+        );
+    }
+
+    #[test]
+    fn total_time_and_recent_edits() {
+        let order = SortOrder {
+            key: SortKey::TotalTime,
+            descending: false,
+        };
+        assert_eq!(
+            sorted_names(order, &HashMap::new()),
+            vec!["Old Pancakes", "Chana Masala", "Crème Brûlée"]
+        );
+
+        let mut edited = HashMap::new();
+        let recipes = mock_server::recipes();
+        edited.insert(recipes[2].uid.clone(), SystemTime::UNIX_EPOCH);
+        edited.insert(
+            recipes[1].uid.clone(),
+            SystemTime::UNIX_EPOCH + Duration::from_secs(60),
+        );
+        let order = SortOrder {
+            key: SortKey::RecentlyEdited,
+            descending: true,
+        };
+        assert_eq!(
+            sorted_names(order, &edited),
+            vec!["Crème Brûlée", "Old Pancakes", "Chana Masala"]
+        );
+    }
+}
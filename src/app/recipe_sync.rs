@@ -0,0 +1,980 @@
+//! Keeps the local recipe database in step with the backend.
+//!
+//! Each pass fetches the server's recipe list, which also tells us the server is reachable. It
+//! then uploads whatever is waiting in the outbox, compares every entry's hash with the one
+//! stored locally, and only downloads recipes that are new or changed. Downloads run several at
+//! a time, no faster than `SyncSettings` allows. Recipes that vanished from the server are
+//! removed locally. Passes repeat every `SyncSettings::interval_minutes`, or only when asked for
+//! through `RecipeSync::wake`.
+//!
+//! An upload that fails is retried on a later pass, backing off further each time; the local
+//! change stays in the database until it goes through.
+
+use crate::app::error::{Error, Result};
+use crate::app::paprika::{self, SharedPaprika};
+use crate::app::recipe_db::{RecipeDb, SyncState};
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use std::{fmt, time};
+use tokio::sync::{Notify, Semaphore, SemaphorePermit};
+
+use iced_futures::futures::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use paprika_api::api::{Recipe, RecipeEntry};
+
+/// The wait after an upload's first failure, doubling with each one after that
+const FIRST_RETRY: time::Duration = time::Duration::from_secs(30);
+const MAX_RETRY: time::Duration = time::Duration::from_secs(60 * 60);
+
+/// The sync engine as an iced subscription. Changing `id` (or the settings) replaces the running
+/// engine with a fresh one, dropping any downloads still in flight.
+pub struct RecipeSync<T> {
+    pub id: T,
+    pub paprika: SharedPaprika,
+    pub db: Arc<Mutex<RecipeDb>>,
+    pub settings: SyncSettings,
+    /// Starts a pass straight away when notified, instead of waiting for the next one
+    pub wake: Arc<Notify>,
+}
+
+/// How hard a pass may push the server
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyncSettings {
+    /// Minutes between passes, or 0 to only sync when asked to
+    pub interval_minutes: u32,
+    /// How many recipes to download at once, and separately how many photos
+    pub concurrent_downloads: usize,
+    /// The most recipe downloads, and separately photo downloads, to start in a second, or 0
+    /// for no limit
+    pub requests_per_second: u32,
+}
+
+impl Default for SyncSettings {
+    fn default() -> Self {
+        Self {
+            interval_minutes: 1,
+            concurrent_downloads: 4,
+            requests_per_second: 10,
+        }
+    }
+}
+
+impl SyncSettings {
+    /// How long to wait between passes, if they run by themselves at all
+    pub fn interval(&self) -> Option<time::Duration> {
+        match self.interval_minutes {
+            0 => None,
+            minutes => Some(time::Duration::from_secs(u64::from(minutes) * 60)),
+        }
+    }
+}
+
+/// What a pass has to do to bring the database in line with the server
+#[derive(Debug, Default, PartialEq)]
+pub struct SyncPlan {
+    /// Uids of recipes that are new or changed on the server
+    pub download: Vec<String>,
+    /// Uids of recipes that were deleted on the server
+    pub remove: Vec<String>,
+}
+
+impl SyncPlan {
+    /// Compares the server's list with the stored hashes. Recipes changed here and not uploaded
+    /// yet are left alone, so the change isn't lost. Purged ones are only downloaded again once
+    /// they change on the server, in case they were restored from the trash elsewhere.
+    pub fn new(entries: &[RecipeEntry], stored: &HashMap<String, (String, SyncState)>) -> Self {
+        let download = entries
+            .iter()
+            .filter(|entry| match stored.get(&entry.uid) {
+                None => true,
+                Some((hash, SyncState::Synced)) | Some((hash, SyncState::Purged)) => {
+                    hash != &entry.hash
+                }
+                Some(_) => false,
+            })
+            .map(|entry| entry.uid.clone())
+            .collect();
+
+        let on_server: HashSet<&str> = entries.iter().map(|entry| entry.uid.as_str()).collect();
+        let mut remove: Vec<String> = stored
+            .iter()
+            .filter(|(uid, (_, state))| {
+                matches!(state, SyncState::Synced | SyncState::Purged)
+                    && !on_server.contains(uid.as_str())
+            })
+            .map(|(uid, _)| uid.clone())
+            .collect();
+        remove.sort();
+
+        Self { download, remove }
+    }
+
+    pub fn len(&self) -> usize {
+        self.download.len() + self.remove.len()
+    }
+}
+
+/// What the sync engine reports back to the app, one step at a time
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
+pub enum SyncEvent {
+    /// A pass has started with this many recipes to download or remove
+    Started(usize),
+    Fetched(Recipe),
+    /// A local edit made it to the server; this is the recipe as stored there
+    Uploaded(Recipe),
+    /// A local deletion made it to the server
+    DeleteUploaded(String),
+    Removed(String),
+    /// A recipe was changed here while the pass was running, so it's left for the next one
+    Skipped,
+    /// A local edit clashed with one made elsewhere, and is waiting to be merged
+    Conflicted(String),
+    /// One recipe couldn't be synced; the pass carries on without it
+    RecipeFailed(Error),
+    Finished,
+    /// The pass was abandoned: the recipe list couldn't be fetched, or the database couldn't
+    /// record that the pass finished
+    Failed(Error),
+}
+
+/// How far along the current pass is
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SyncProgress {
+    pub done: usize,
+    pub total: usize,
+    pub running: bool,
+    /// Whether the last pass was abandoned
+    pub failed: bool,
+    /// When a pass last ran to the end
+    pub last_synced: Option<time::SystemTime>,
+}
+
+impl SyncProgress {
+    pub fn record(&mut self, event: &SyncEvent) {
+        match event {
+            SyncEvent::Started(total) => {
+                *self = Self {
+                    done: 0,
+                    total: *total,
+                    running: true,
+                    failed: false,
+                    last_synced: self.last_synced,
+                }
+            }
+            SyncEvent::Fetched(_)
+            | SyncEvent::Uploaded(_)
+            | SyncEvent::DeleteUploaded(_)
+            | SyncEvent::Removed(_)
+            | SyncEvent::Skipped
+            | SyncEvent::Conflicted(_)
+            | SyncEvent::RecipeFailed(_) => self.done += 1,
+            SyncEvent::Finished => {
+                self.running = false;
+                self.last_synced = Some(time::SystemTime::now());
+            }
+            SyncEvent::Failed(_) => {
+                self.running = false;
+                self.failed = true;
+            }
+        }
+    }
+}
+
+impl SyncProgress {
+    /// When the last pass finished, relative to `now`
+    pub fn last_synced_text(&self, now: time::SystemTime) -> String {
+        let last_synced = match self.last_synced {
+            Some(last_synced) => last_synced,
+            None => return "Never synced".into(),
+        };
+
+        let minutes = now
+            .duration_since(last_synced)
+            .map_or(0, |ago| ago.as_secs() / 60);
+        let (count, unit) = match minutes {
+            0 => return "Last synced just now".into(),
+            1..=59 => (minutes, "minute"),
+            60..=1439 => (minutes / 60, "hour"),
+            _ => (minutes / 1440, "day"),
+        };
+        let plural = if count == 1 { "" } else { "s" };
+        format!("Last synced {} {}{} ago", count, unit, plural)
+    }
+}
+
+impl fmt::Display for SyncProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.running {
+            write!(f, "Syncing {} of {}", self.done, self.total)
+        } else if self.failed {
+            write!(f, "Offline")
+        } else {
+            write!(f, "Up to date")
+        }
+    }
+}
+
+/// Where the engine is between steps
+enum Pass {
+    Listing,
+    /// Uploads and removals go one at a time, then the downloads are drained
+    Syncing(VecDeque<Step>, Downloads),
+    Waiting,
+}
+
+enum Step {
+    Upload(String),
+    UploadDelete(String),
+    Remove(String),
+}
+
+/// A pass's downloads, reporting each recipe as it arrives
+type Downloads = futures::stream::BoxStream<'static, SyncEvent>;
+
+/// Spaces requests out evenly, `per_second` to a second
+struct RateLimit {
+    interval: time::Duration,
+    next: Mutex<tokio::time::Instant>,
+}
+
+impl RateLimit {
+    fn new(per_second: u32) -> Self {
+        let interval = match per_second {
+            0 => time::Duration::ZERO,
+            per_second => time::Duration::from_secs(1) / per_second,
+        };
+        Self {
+            interval,
+            next: Mutex::new(tokio::time::Instant::now()),
+        }
+    }
+
+    /// Waits for the next free slot
+    async fn wait(&self) {
+        let slot = {
+            let mut next = self.next.lock().unwrap();
+            let slot = (*next).max(tokio::time::Instant::now());
+            *next = slot + self.interval;
+            slot
+        };
+        tokio::time::sleep_until(slot).await;
+    }
+}
+
+/// Holds downloads made outside a pass, like photos, to the same limits as recipe downloads
+pub struct DownloadLimit {
+    slots: Semaphore,
+    rate: RateLimit,
+}
+
+impl DownloadLimit {
+    pub fn new(settings: SyncSettings) -> Self {
+        Self {
+            slots: Semaphore::new(settings.concurrent_downloads.max(1)),
+            rate: RateLimit::new(settings.requests_per_second),
+        }
+    }
+
+    /// Waits for a free download slot and then for the rate limit. The slot is given back when
+    /// the permit is dropped.
+    pub async fn acquire(&self) -> SemaphorePermit<'_> {
+        let permit = self
+            .slots
+            .acquire()
+            .await
+            .expect("the download semaphore is never closed");
+        self.rate.wait().await;
+        permit
+    }
+}
+
+/// How long to wait before retrying an upload that has failed `attempts` times
+pub fn retry_delay(attempts: u32) -> time::Duration {
+    FIRST_RETRY
+        .checked_mul(2u32.saturating_pow(attempts.saturating_sub(1)))
+        .map_or(MAX_RETRY, |delay| delay.min(MAX_RETRY))
+}
+
+/// Runs one step of a pass, returning what happened and what to do next.
+///
+/// The database lock is only ever taken between awaits; the client's lock is async.
+async fn step(
+    pass: Pass,
+    paprika: &tokio::sync::Mutex<paprika::Paprika>,
+    db: &Arc<Mutex<RecipeDb>>,
+    settings: SyncSettings,
+) -> (SyncEvent, Pass) {
+    match pass {
+        // the stream has already waited out the interval by the time a waiting pass gets here
+        Pass::Listing | Pass::Waiting => {
+            let mut paprika = paprika.lock().await;
+            if let Err(e) = paprika.fetch_recipe_list().await {
+                return (SyncEvent::Failed(e), Pass::Waiting);
+            }
+
+            let local = {
+                let db = db.lock().unwrap();
+                db.statuses()
+                    .and_then(|stored| Ok((stored, db.pending(time::SystemTime::now())?)))
+            };
+            let (stored, pending) = match local {
+                Ok(local) => local,
+                Err(e) => return (SyncEvent::Failed(e), Pass::Waiting),
+            };
+            let plan = SyncPlan::new(&paprika.recipe_entries, &stored);
+
+            let total = pending.len() + plan.len();
+            let steps = pending
+                .into_iter()
+                .map(|(uid, state)| match state {
+                    SyncState::Deleted => Step::UploadDelete(uid),
+                    _ => Step::Upload(uid),
+                })
+                .chain(plan.remove.into_iter().map(Step::Remove))
+                .collect();
+            let downloads = downloads(plan.download, paprika.session(), db.clone(), settings);
+            (SyncEvent::Started(total), Pass::Syncing(steps, downloads))
+        }
+        Pass::Syncing(mut steps, mut downloads) => {
+            let event = match steps.pop_front() {
+                Some(Step::Upload(uid)) => {
+                    let recipe = db.lock().unwrap().recipe(&uid);
+                    let mut paprika = paprika.lock().await;
+                    let uploaded = match recipe {
+                        Ok(Some(recipe)) => upload(&mut paprika, db, recipe).await,
+                        Ok(None) => return (SyncEvent::Skipped, Pass::Syncing(steps, downloads)),
+                        Err(e) => Err(e),
+                    };
+                    match uploaded {
+                        Ok(recipe) => SyncEvent::Uploaded(recipe),
+                        Err(e) => upload_failed(db, uid, e),
+                    }
+                }
+                Some(Step::UploadDelete(uid)) => {
+                    let mut paprika = paprika.lock().await;
+                    let deleted = match paprika.delete_recipe(&uid).await {
+                        Ok(()) => db.lock().unwrap().purge(&uid),
+                        Err(e) => Err(e),
+                    };
+                    match deleted {
+                        Ok(()) => SyncEvent::DeleteUploaded(uid),
+                        Err(e) => upload_failed(db, uid, e),
+                    }
+                }
+                Some(Step::Remove(uid)) => match db.lock().unwrap().remove(&uid) {
+                    Ok(()) => SyncEvent::Removed(uid),
+                    Err(e) => SyncEvent::RecipeFailed(e),
+                },
+                None => match downloads.next().await {
+                    Some(event) => event,
+                    None => {
+                        let event =
+                            match db.lock().unwrap().set_last_synced(time::SystemTime::now()) {
+                                Ok(()) => SyncEvent::Finished,
+                                Err(e) => SyncEvent::Failed(e),
+                            };
+                        return (event, Pass::Waiting);
+                    }
+                },
+            };
+            (event, Pass::Syncing(steps, downloads))
+        }
+    }
+}
+
+/// Keeps a failed upload in the outbox and schedules its next try
+fn upload_failed(db: &Mutex<RecipeDb>, uid: String, error: Error) -> SyncEvent {
+    // conflicts wait for someone to merge them, not for a retry
+    if let Error::Conflict(_) = error {
+        return SyncEvent::Conflicted(uid);
+    }
+    // without the retry recorded the upload is simply tried again next pass, but a database
+    // that can't be written to is worth knowing about over the upload error
+    match db.lock().unwrap().mark_failed(&uid, &error, retry_delay) {
+        Ok(()) => SyncEvent::RecipeFailed(error),
+        Err(e) => SyncEvent::RecipeFailed(e),
+    }
+}
+
+/// Uploads a recipe edited here, marking it synced unless it was edited again in the meantime.
+///
+/// If the server copy has moved on since the edit started (going by `paprika.recipe_entries`,
+/// which the caller keeps fresh), nothing is uploaded: the server copy is stored alongside the
+/// edit for merging and `Error::Conflict` is returned.
+pub async fn upload(
+    paprika: &mut paprika::Paprika,
+    db: &Mutex<RecipeDb>,
+    recipe: Recipe,
+) -> Result<Recipe> {
+    let base = db.lock().unwrap().base(&recipe.uid)?;
+    let server_hash = paprika
+        .recipe_entries
+        .iter()
+        .find(|entry| entry.uid == recipe.uid)
+        .map(|entry| entry.hash.clone());
+    if let (Some(base), Some(server_hash)) = (base, server_hash) {
+        if base.hash != server_hash {
+            let theirs = paprika.get_recipe_by_id(&recipe.uid).await?;
+            db.lock().unwrap().mark_conflict(&recipe.uid, &theirs)?;
+            return Err(Error::Conflict(recipe.name));
+        }
+    }
+
+    let local_hash = recipe.hash.clone();
+    let mut uploaded = recipe;
+    paprika.update_recipe(&mut uploaded).await?;
+
+    let db = db.lock().unwrap();
+    if let Some((hash, SyncState::Modified)) = db.status(&uploaded.uid)? {
+        if hash == local_hash {
+            db.save(&uploaded, SyncState::Synced)?;
+        }
+    }
+    Ok(uploaded)
+}
+
+/// Waits out the interval between passes, or until a pass is asked for
+async fn wait_for_pass(settings: SyncSettings, wake: &Notify) {
+    match settings.interval() {
+        Some(interval) => tokio::select! {
+            _ = tokio::time::sleep(interval) => (),
+            _ = wake.notified() => (),
+        },
+        None => wake.notified().await,
+    }
+}
+
+/// Downloads `uids` into the database, `concurrent_downloads` at a time
+fn downloads(
+    uids: Vec<String>,
+    session: paprika::Session,
+    db: Arc<Mutex<RecipeDb>>,
+    settings: SyncSettings,
+) -> Downloads {
+    let limit = Arc::new(RateLimit::new(settings.requests_per_second));
+    futures::stream::iter(uids)
+        .map(move |uid| {
+            let (session, db, limit) = (session.clone(), db.clone(), limit.clone());
+            async move {
+                limit.wait().await;
+                match download(&session, &db, &uid).await {
+                    Ok(Some(recipe)) => SyncEvent::Fetched(recipe),
+                    Ok(None) => SyncEvent::Skipped,
+                    Err(e) => SyncEvent::RecipeFailed(e),
+                }
+            }
+        })
+        .buffer_unordered(settings.concurrent_downloads.max(1))
+        .boxed()
+}
+
+/// Downloads a recipe into the database, unless it was edited here since the pass started
+async fn download(
+    session: &paprika::Session,
+    db: &Mutex<RecipeDb>,
+    uid: &str,
+) -> Result<Option<Recipe>> {
+    let recipe = session.recipe(uid).await?;
+
+    let db = db.lock().unwrap();
+    match db.status(uid)? {
+        Some((_, SyncState::Synced)) | None => (),
+        // a recipe removed from this device only comes back once it's out of the trash; until
+        // then its new hash is remembered so it isn't downloaded every pass
+        Some((_, SyncState::Purged)) if recipe.in_trash => {
+            db.save(&recipe, SyncState::Purged)?;
+            return Ok(None);
+        }
+        Some((_, SyncState::Purged)) => (),
+        Some(_) => return Ok(None),
+    }
+    db.save(&recipe, SyncState::Synced)?;
+    Ok(Some(recipe))
+}
+
+impl<H, I, T> iced_native::subscription::Recipe<H, I> for RecipeSync<T>
+where
+    T: 'static + Hash + Copy + Send,
+    H: Hasher,
+{
+    type Output = SyncEvent;
+
+    fn hash(&self, state: &mut H) {
+        struct Marker;
+        std::any::TypeId::of::<Marker>().hash(state);
+
+        self.id.hash(state);
+        self.settings.hash(state);
+    }
+
+    fn stream(
+        self: Box<Self>,
+        _input: futures::stream::BoxStream<I>,
+    ) -> futures::stream::BoxStream<Self::Output> {
+        let settings = self.settings;
+        // with manual syncing there's no pass until one is asked for, not even at startup
+        let first = match settings.interval() {
+            Some(_) => Pass::Listing,
+            None => Pass::Waiting,
+        };
+        Box::pin(futures::stream::unfold(
+            (
+                first,
+                self.paprika.clone(),
+                self.db.clone(),
+                self.wake.clone(),
+            ),
+            move |(pass, paprika, db, wake)| async move {
+                let pass = match pass {
+                    Pass::Waiting => {
+                        wait_for_pass(settings, &wake).await;
+                        Pass::Listing
+                    }
+                    pass => pass,
+                };
+
+                let (event, next) = step(pass, &paprika, &db, settings).await;
+                Some((event, (next, paprika, db, wake)))
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::backend::mock_server::{self, MockServer};
+    use crate::app::backend::PaprikaCloud;
+
+    const CHANA_MASALA: &str = "0C1A4B3E-7D5E-4F43-9F0C-3B0B1E5A6C01";
+
+    async fn paprika_for(server: &MockServer) -> tokio::sync::Mutex<paprika::Paprika> {
+        tokio::sync::Mutex::new(mock_server::logged_in(server).await)
+    }
+
+    /// Runs a whole pass, returning everything it reported
+    async fn run_pass(
+        paprika: &tokio::sync::Mutex<paprika::Paprika>,
+        db: &Arc<Mutex<RecipeDb>>,
+    ) -> Vec<SyncEvent> {
+        let mut events = Vec::new();
+        let mut pass = Pass::Listing;
+        loop {
+            let (event, next) = step(pass, paprika, db, SyncSettings::default()).await;
+            events.push(event);
+            match next {
+                Pass::Waiting => return events,
+                next => pass = next,
+            }
+        }
+    }
+
+    fn fetched(events: &[SyncEvent]) -> Vec<String> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                SyncEvent::Fetched(recipe) => Some(recipe.uid.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn plan_only_touches_what_changed() {
+        let entry = |uid: &str, hash: &str| RecipeEntry {
+            uid: uid.into(),
+            hash: hash.into(),
+        };
+        let entries = vec![
+            entry("same", "1"),
+            entry("changed", "2"),
+            entry("new", "1"),
+            entry("purged", "1"),
+            entry("purged-and-changed", "2"),
+        ];
+        let stored = [
+            ("same", "1", SyncState::Synced),
+            ("changed", "1", SyncState::Synced),
+            ("gone", "1", SyncState::Synced),
+            ("local-only", "1", SyncState::Modified),
+            ("purged", "1", SyncState::Purged),
+            ("purged-and-changed", "1", SyncState::Purged),
+            ("purged-and-gone", "1", SyncState::Purged),
+        ]
+        .iter()
+        .map(|(uid, hash, state)| (uid.to_string(), (hash.to_string(), *state)))
+        .collect();
+
+        let plan = SyncPlan::new(&entries, &stored);
+        assert_eq!(plan.download, vec!["changed", "new", "purged-and-changed"]);
+        assert_eq!(plan.remove, vec!["gone", "purged-and-gone"]);
+    }
+
+    #[tokio::test]
+    async fn passes_only_download_changes() {
+        let server = MockServer::start().await;
+        let paprika = paprika_for(&server).await;
+        let db = Arc::new(Mutex::new(RecipeDb::open_in_memory().unwrap()));
+
+        let events = run_pass(&paprika, &db).await;
+        assert!(matches!(events[0], SyncEvent::Started(total) if total == server.recipe_count()));
+        assert_eq!(fetched(&events).len(), server.recipe_count());
+        assert!(matches!(events.last(), Some(SyncEvent::Finished)));
+        assert!(db.lock().unwrap().last_synced().unwrap().is_some());
+
+        let events = run_pass(&paprika, &db).await;
+        assert!(matches!(events[0], SyncEvent::Started(0)));
+        assert!(fetched(&events).is_empty());
+
+        let mut edited = server.recipe(CHANA_MASALA).unwrap();
+        edited.notes = "Edited on the phone".into();
+        server.put_recipe(edited);
+        let events = run_pass(&paprika, &db).await;
+        assert_eq!(fetched(&events), vec![CHANA_MASALA]);
+        assert_eq!(
+            db.lock()
+                .unwrap()
+                .recipe(CHANA_MASALA)
+                .unwrap()
+                .unwrap()
+                .notes,
+            "Edited on the phone"
+        );
+    }
+
+    #[tokio::test]
+    async fn deletions_on_the_server_are_applied_locally() {
+        let server = MockServer::start().await;
+        let paprika = paprika_for(&server).await;
+        let db = Arc::new(Mutex::new(RecipeDb::open_in_memory().unwrap()));
+        run_pass(&paprika, &db).await;
+
+        server.remove_recipe(CHANA_MASALA);
+        let events = run_pass(&paprika, &db).await;
+
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, SyncEvent::Removed(uid) if uid == CHANA_MASALA)));
+        assert!(db.lock().unwrap().recipe(CHANA_MASALA).unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn local_edits_are_not_overwritten() {
+        let server = MockServer::start().await;
+        let paprika = paprika_for(&server).await;
+        let db = Arc::new(Mutex::new(RecipeDb::open_in_memory().unwrap()));
+
+        let mut recipe = server.recipe(CHANA_MASALA).unwrap();
+        recipe.notes = "Edited in the kitchen".into();
+        recipe.hash = "local".into();
+        db.lock()
+            .unwrap()
+            .save(&recipe, SyncState::Modified)
+            .unwrap();
+
+        let events = run_pass(&paprika, &db).await;
+        assert!(!fetched(&events).contains(&CHANA_MASALA.to_string()));
+        assert_eq!(
+            db.lock()
+                .unwrap()
+                .recipe(CHANA_MASALA)
+                .unwrap()
+                .unwrap()
+                .notes,
+            "Edited in the kitchen"
+        );
+    }
+
+    #[tokio::test]
+    async fn offline_edits_are_uploaded_once_the_server_is_back() {
+        let server = MockServer::start().await;
+        let db = Arc::new(Mutex::new(RecipeDb::open_in_memory().unwrap()));
+
+        let mut recipe = server.recipe(CHANA_MASALA).unwrap();
+        recipe.notes = "Edited with no Wi-Fi".into();
+        crate::app::backend::prepare_upload(&mut recipe);
+        db.lock()
+            .unwrap()
+            .save(&recipe, SyncState::Modified)
+            .unwrap();
+
+        // nothing listens on the discard port, so this pass can't reach a server
+        let offline = tokio::sync::Mutex::new(paprika::Paprika::with_backend(Box::new(
+            PaprikaCloud::with_base_url("http://127.0.0.1:9/api/v2"),
+        )));
+        offline.lock().await.token = "offline".into();
+        let events = run_pass(&offline, &db).await;
+        assert!(matches!(events[..], [SyncEvent::Failed(_)]));
+        assert_eq!(db.lock().unwrap().unsynced().unwrap(), vec![CHANA_MASALA]);
+
+        let events = run_pass(&paprika_for(&server).await, &db).await;
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, SyncEvent::Uploaded(r) if r.uid == CHANA_MASALA)));
+        assert_eq!(
+            server.recipe(CHANA_MASALA).unwrap().notes,
+            "Edited with no Wi-Fi"
+        );
+        assert!(db.lock().unwrap().unsynced().unwrap().is_empty());
+
+        // the uploaded copy already matches, so it isn't downloaded again
+        let events = run_pass(&paprika_for(&server).await, &db).await;
+        assert!(fetched(&events).is_empty());
+    }
+
+    #[tokio::test]
+    async fn edits_on_both_sides_are_held_for_merging() {
+        let server = MockServer::start().await;
+        let paprika = paprika_for(&server).await;
+        let db = Arc::new(Mutex::new(RecipeDb::open_in_memory().unwrap()));
+        run_pass(&paprika, &db).await;
+
+        let mut mine = db.lock().unwrap().recipe(CHANA_MASALA).unwrap().unwrap();
+        mine.notes = "Less salt".into();
+        crate::app::backend::prepare_upload(&mut mine);
+        db.lock().unwrap().save(&mine, SyncState::Modified).unwrap();
+
+        let mut theirs = server.recipe(CHANA_MASALA).unwrap();
+        theirs.notes = "More chilli".into();
+        server.put_recipe(theirs);
+
+        let events = run_pass(&paprika, &db).await;
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, SyncEvent::Conflicted(uid) if uid == CHANA_MASALA)));
+        assert_eq!(server.recipe(CHANA_MASALA).unwrap().notes, "More chilli");
+
+        let (_, mine, theirs) = db.lock().unwrap().conflict(CHANA_MASALA).unwrap().unwrap();
+        assert_eq!(mine.notes, "Less salt");
+        assert_eq!(theirs.notes, "More chilli");
+    }
+
+    #[tokio::test]
+    async fn edits_conflict_with_downloads_made_while_editing() {
+        let server = MockServer::start().await;
+        let paprika = paprika_for(&server).await;
+        let db = Arc::new(Mutex::new(RecipeDb::open_in_memory().unwrap()));
+        run_pass(&paprika, &db).await;
+
+        let opened = db.lock().unwrap().recipe(CHANA_MASALA).unwrap().unwrap();
+
+        // the phone's edit is downloaded while the editor is still open
+        let mut theirs = server.recipe(CHANA_MASALA).unwrap();
+        theirs.notes = "More chilli".into();
+        server.put_recipe(theirs);
+        run_pass(&paprika, &db).await;
+
+        let mut mine = opened.clone();
+        mine.notes = "Less salt".into();
+        crate::app::backend::prepare_upload(&mut mine);
+        db.lock().unwrap().save_edit(&mine, &opened).unwrap();
+
+        let mut paprika = paprika.lock().await;
+        paprika.fetch_recipe_list().await.unwrap();
+        let uploaded = upload(&mut paprika, &db, mine).await;
+        assert!(matches!(uploaded, Err(Error::Conflict(_))));
+        assert_eq!(server.recipe(CHANA_MASALA).unwrap().notes, "More chilli");
+    }
+
+    #[tokio::test]
+    async fn local_deletions_are_uploaded() {
+        let server = MockServer::start().await;
+        let paprika = paprika_for(&server).await;
+        let db = Arc::new(Mutex::new(RecipeDb::open_in_memory().unwrap()));
+        run_pass(&paprika, &db).await;
+
+        db.lock().unwrap().delete_locally(CHANA_MASALA).unwrap();
+        let events = run_pass(&paprika, &db).await;
+
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, SyncEvent::DeleteUploaded(uid) if uid == CHANA_MASALA)));
+        assert!(server.recipe(CHANA_MASALA).unwrap().in_trash);
+        assert!(db.lock().unwrap().unsynced().unwrap().is_empty());
+
+        // the server keeps it in its trash, but it's gone from here
+        let events = run_pass(&paprika, &db).await;
+        assert!(fetched(&events).is_empty());
+        assert!(db
+            .lock()
+            .unwrap()
+            .recipes()
+            .unwrap()
+            .iter()
+            .all(|recipe| recipe.uid != CHANA_MASALA));
+    }
+
+    #[tokio::test]
+    async fn removing_from_the_trash_only_affects_this_device() {
+        let server = MockServer::start().await;
+        let paprika = paprika_for(&server).await;
+        let db = Arc::new(Mutex::new(RecipeDb::open_in_memory().unwrap()));
+        run_pass(&paprika, &db).await;
+
+        let mut trashed = db.lock().unwrap().recipe(CHANA_MASALA).unwrap().unwrap();
+        trashed.in_trash = true;
+        crate::app::backend::prepare_upload(&mut trashed);
+        db.lock()
+            .unwrap()
+            .save(&trashed, SyncState::Modified)
+            .unwrap();
+        run_pass(&paprika, &db).await;
+        db.lock().unwrap().delete_locally(CHANA_MASALA).unwrap();
+        run_pass(&paprika, &db).await;
+
+        // the sync API can't delete a recipe, so the server still has it in its trash
+        assert!(server.recipe(CHANA_MASALA).unwrap().in_trash);
+
+        // and it comes back, still trashed, once this device forgets it removed it
+        db.lock().unwrap().clear().unwrap();
+        let events = run_pass(&paprika, &db).await;
+        assert!(fetched(&events).contains(&CHANA_MASALA.to_string()));
+        assert!(
+            db.lock()
+                .unwrap()
+                .recipe(CHANA_MASALA)
+                .unwrap()
+                .unwrap()
+                .in_trash
+        );
+    }
+
+    #[tokio::test]
+    async fn recipes_restored_elsewhere_come_back() {
+        let server = MockServer::start().await;
+        let paprika = paprika_for(&server).await;
+        let db = Arc::new(Mutex::new(RecipeDb::open_in_memory().unwrap()));
+        run_pass(&paprika, &db).await;
+
+        db.lock().unwrap().delete_locally(CHANA_MASALA).unwrap();
+        run_pass(&paprika, &db).await;
+        assert!(server.recipe(CHANA_MASALA).unwrap().in_trash);
+
+        // still in the trash, so it stays removed here
+        let events = run_pass(&paprika, &db).await;
+        assert!(fetched(&events).is_empty());
+        let events = run_pass(&paprika, &db).await;
+        assert!(matches!(events[0], SyncEvent::Started(0)));
+
+        let mut restored = server.recipe(CHANA_MASALA).unwrap();
+        restored.in_trash = false;
+        server.put_recipe(restored);
+
+        let events = run_pass(&paprika, &db).await;
+        assert_eq!(fetched(&events), vec![CHANA_MASALA]);
+        let db = db.lock().unwrap();
+        assert!(!db.recipe(CHANA_MASALA).unwrap().unwrap().in_trash);
+        assert!(db
+            .recipes()
+            .unwrap()
+            .iter()
+            .any(|recipe| recipe.uid == CHANA_MASALA));
+    }
+
+    #[test]
+    fn retries_back_off() {
+        assert_eq!(retry_delay(1), FIRST_RETRY);
+        assert_eq!(retry_delay(3), FIRST_RETRY * 4);
+        assert_eq!(retry_delay(20), MAX_RETRY);
+        assert_eq!(retry_delay(u32::MAX), MAX_RETRY);
+    }
+
+    #[test]
+    fn progress_counts_each_step() {
+        let mut progress = SyncProgress::default();
+        assert_eq!(progress.to_string(), "Up to date");
+
+        progress.record(&SyncEvent::Started(2));
+        progress.record(&SyncEvent::Removed("gone".into()));
+        assert_eq!(progress.to_string(), "Syncing 1 of 2");
+
+        progress.record(&SyncEvent::Skipped);
+        progress.record(&SyncEvent::Finished);
+        assert_eq!(progress.to_string(), "Up to date");
+
+        progress.record(&SyncEvent::Failed(Error::Network("timed out".into())));
+        assert_eq!(progress.to_string(), "Offline");
+    }
+
+    #[test]
+    fn last_sync_is_shown_relative_to_now() {
+        let now = time::SystemTime::now();
+        let ago = |secs| SyncProgress {
+            last_synced: Some(now - time::Duration::from_secs(secs)),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            SyncProgress::default().last_synced_text(now),
+            "Never synced"
+        );
+        assert_eq!(ago(20).last_synced_text(now), "Last synced just now");
+        assert_eq!(ago(60).last_synced_text(now), "Last synced 1 minute ago");
+        assert_eq!(
+            ago(3 * 3600).last_synced_text(now),
+            "Last synced 3 hours ago"
+        );
+        assert_eq!(
+            ago(2 * 86400).last_synced_text(now),
+            "Last synced 2 days ago"
+        );
+    }
+
+    #[tokio::test]
+    async fn manual_syncing_waits_to_be_asked() {
+        let manual = SyncSettings {
+            interval_minutes: 0,
+            ..Default::default()
+        };
+        let wake = Notify::new();
+
+        let waited = tokio::time::timeout(
+            time::Duration::from_millis(50),
+            wait_for_pass(manual, &wake),
+        )
+        .await;
+        assert!(waited.is_err());
+
+        wake.notify_one();
+        wait_for_pass(manual, &wake).await;
+    }
+
+    #[tokio::test]
+    async fn download_limit_holds_back_extra_downloads() {
+        let limit = DownloadLimit::new(SyncSettings {
+            concurrent_downloads: 2,
+            requests_per_second: 0,
+            ..SyncSettings::default()
+        });
+        let first = limit.acquire().await;
+        let _second = limit.acquire().await;
+        let third = tokio::time::timeout(time::Duration::from_millis(20), limit.acquire());
+        assert!(third.await.is_err());
+
+        drop(first);
+        let third = tokio::time::timeout(time::Duration::from_millis(20), limit.acquire());
+        assert!(third.await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn downloads_are_rate_limited() {
+        let limit = RateLimit::new(50);
+        let start = tokio::time::Instant::now();
+        for _ in 0..5 {
+            limit.wait().await;
+        }
+        assert!(start.elapsed() >= time::Duration::from_millis(80));
+
+        let unlimited = RateLimit::new(0);
+        let start = tokio::time::Instant::now();
+        for _ in 0..5 {
+            unlimited.wait().await;
+        }
+        assert!(start.elapsed() < time::Duration::from_millis(80));
+    }
+}
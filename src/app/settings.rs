@@ -0,0 +1,218 @@
+use crate::app::error::{Error, Result};
+use crate::app::layout;
+use crate::app::profile::Profile;
+use crate::app::recipe_card::ListMode;
+use crate::app::recipe_sort::SortOrder;
+use crate::app::recipe_sync::SyncSettings;
+use crate::app::shortcuts::Shortcuts;
+
+use serde::{Deserialize, Serialize};
+use std::{fs, path};
+
+/// Preferences that survive a restart, stored as TOML
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Overrides where recipes and photos are cached
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_dir: Option<path::PathBuf>,
+    /// The id of the profile in use
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// The name of the theme in use
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub theme: Option<String>,
+    /// What new recipes' servings start out as, like "4 servings"
+    pub default_servings: String,
+    /// Whether the recipe list is a list or a grid of photos
+    pub list_mode: ListMode,
+    pub window: WindowSettings,
+    pub cache: CacheSettings,
+    pub sort: SortOrder,
+    pub sync: SyncSettings,
+    pub shortcuts: Shortcuts,
+    pub profiles: Vec<Profile>,
+}
+
+/// The window as it was left, and how it's split up to begin with
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowSettings {
+    pub width: u32,
+    pub height: u32,
+    /// How much of the window the nav pane takes when there's no saved layout
+    pub nav_ratio: f32,
+}
+
+impl Default for WindowSettings {
+    fn default() -> Self {
+        Self {
+            width: 1024,
+            height: 768,
+            nav_ratio: layout::NAV_RATIO,
+        }
+    }
+}
+
+/// Limits on what's kept on disk
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheSettings {
+    /// The most space downloaded photos can take, in megabytes, or 0 for no limit. Checked when
+    /// a profile is opened and when the settings change.
+    pub photo_limit_mb: u64,
+}
+
+impl Default for CacheSettings {
+    fn default() -> Self {
+        Self {
+            photo_limit_mb: 500,
+        }
+    }
+}
+
+impl CacheSettings {
+    pub fn photo_limit(&self) -> Option<u64> {
+        match self.photo_limit_mb {
+            0 => None,
+            mb => Some(mb.saturating_mul(1024 * 1024)),
+        }
+    }
+}
+
+impl Settings {
+    /// Reads the settings at `path`, falling back to the defaults if there's no file yet
+    pub fn load(path: &path::Path) -> Result<Self> {
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(path)
+            .map_err(|e| Error::Settings(format!("{}: {}", path.display(), e)))?;
+        toml::from_str(&contents).map_err(|e| Error::Settings(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Moves a settings file that couldn't be loaded out of the way, so saving doesn't write
+    /// the defaults over it, and returns where it went
+    pub fn set_aside(path: &path::Path) -> Result<path::PathBuf> {
+        let backup = path.with_extension("toml.bak");
+        fs::rename(path, &backup)
+            .map_err(|e| Error::Settings(format!("{}: {}", path.display(), e)))?;
+        Ok(backup)
+    }
+
+    /// The profile picked last, or else the first one
+    pub fn current_profile(&self) -> Profile {
+        self.profiles
+            .iter()
+            .find(|profile| Some(&profile.id) == self.profile.as_ref())
+            .or_else(|| self.profiles.first())
+            .cloned()
+            .unwrap_or_else(Profile::default_profile)
+    }
+
+    pub fn save(&self, path: &path::Path) -> Result<()> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)
+                .map_err(|e| Error::Settings(format!("{}: {}", dir.display(), e)))?;
+        }
+
+        let contents = toml::to_string_pretty(self).map_err(|e| Error::Settings(e.to_string()))?;
+        fs::write(path, contents).map_err(|e| Error::Settings(format!("{}: {}", path.display(), e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::backend::BackendConfig;
+    use crate::app::recipe_sort::SortKey;
+    use crate::app::shortcuts::Shortcut;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn settings_survive_a_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ancho").join("settings.toml");
+        assert_eq!(Settings::load(&path).unwrap(), Settings::default());
+
+        let settings = Settings {
+            cache_dir: Some(dir.path().join("cache")),
+            sort: SortOrder {
+                key: SortKey::Created,
+                descending: true,
+            },
+            sync: SyncSettings {
+                interval_minutes: 0,
+                concurrent_downloads: 8,
+                requests_per_second: 0,
+            },
+            shortcuts: Shortcuts {
+                sync_now: Shortcut::default(),
+                show_trash: Shortcut::try_from("F9".to_string()).unwrap(),
+                ..Shortcuts::default()
+            },
+            profile: Some("kitchen".into()),
+            theme: Some("High contrast".into()),
+            default_servings: "4 servings".into(),
+            list_mode: ListMode::Grid,
+            window: WindowSettings {
+                width: 800,
+                height: 1280,
+                nav_ratio: 0.25,
+            },
+            cache: CacheSettings { photo_limit_mb: 0 },
+            profiles: vec![
+                Profile::default_profile(),
+                Profile {
+                    id: "kitchen".into(),
+                    name: "Kitchen".into(),
+                    backend: BackendConfig::Server {
+                        url: "http://kitchen.local/api/v2".into(),
+                    },
+                },
+            ],
+        };
+        settings.save(&path).unwrap();
+
+        let loaded = Settings::load(&path).unwrap();
+        assert_eq!(loaded, settings);
+        assert_eq!(loaded.current_profile().name, "Kitchen");
+    }
+
+    #[test]
+    fn unreadable_settings_are_set_aside() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.toml");
+        fs::write(&path, "profiles = 3").unwrap();
+        assert!(Settings::load(&path).is_err());
+
+        let backup = Settings::set_aside(&path).unwrap();
+        assert_eq!(backup, dir.path().join("settings.toml.bak"));
+        assert_eq!(fs::read_to_string(backup).unwrap(), "profiles = 3");
+        assert_eq!(Settings::load(&path).unwrap(), Settings::default());
+    }
+
+    #[test]
+    fn a_bad_shortcut_does_not_lose_the_rest() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.toml");
+        fs::write(
+            &path,
+            "default_servings = \"2\"\n[shortcuts]\nnew_recipe = \"Ctrl+Hyper\"\n",
+        )
+        .unwrap();
+
+        let settings = Settings::load(&path).unwrap();
+        assert_eq!(settings.default_servings, "2");
+        assert_eq!(settings.shortcuts.problems.len(), 1);
+    }
+
+    #[test]
+    fn huge_photo_limits_do_not_overflow() {
+        let cache = CacheSettings {
+            photo_limit_mb: u64::MAX,
+        };
+        assert_eq!(cache.photo_limit(), Some(u64::MAX));
+    }
+}
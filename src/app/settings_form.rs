@@ -0,0 +1,345 @@
+use crate::app::profile_form::BackendKind;
+use crate::app::settings::Settings;
+use crate::app::theme::{self, NamedTheme, Theme};
+use crate::app::Message;
+
+use iced::{
+    button, pane_grid, pick_list, scrollable, text_input, Align, Button, Element, Length, PickList,
+    Row, Scrollable, Text, TextInput,
+};
+use std::path;
+use std::str::FromStr;
+
+const LABEL_WIDTH: u16 = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingsField {
+    SyncInterval,
+    ConcurrentDownloads,
+    RequestsPerSecond,
+    DefaultServings,
+    PhotoLimit,
+    CacheDir,
+    /// The server URL or folder of the profile's backend
+    Location,
+}
+
+#[derive(Debug, Clone)]
+pub enum SettingsMessage {
+    ThemePicked(String),
+    BackendPicked(BackendKind),
+    FieldChanged(SettingsField, String),
+}
+
+/// Edits the settings file, along with where the profile in use syncs with
+pub struct SettingsForm {
+    theme: String,
+    themes: Vec<String>,
+    sync_interval: String,
+    concurrent_downloads: String,
+    requests_per_second: String,
+    default_servings: String,
+    photo_limit: String,
+    cache_dir: String,
+    backend: BackendKind,
+    location: String,
+
+    scroll: scrollable::State,
+    theme_picker: pick_list::State<String>,
+    backend_picker: pick_list::State<BackendKind>,
+    inputs: [text_input::State; 7],
+    save: button::State,
+    cancel: button::State,
+}
+
+impl SettingsForm {
+    pub fn new(settings: &Settings, themes: &[NamedTheme]) -> Self {
+        let (backend, location) = BackendKind::of(&settings.current_profile().backend);
+        Self {
+            theme: theme::find(themes, settings.theme.as_deref())
+                .map(|named| named.name.clone())
+                .unwrap_or_default(),
+            themes: themes.iter().map(|named| named.name.clone()).collect(),
+            sync_interval: settings.sync.interval_minutes.to_string(),
+            concurrent_downloads: settings.sync.concurrent_downloads.to_string(),
+            requests_per_second: settings.sync.requests_per_second.to_string(),
+            default_servings: settings.default_servings.clone(),
+            photo_limit: settings.cache.photo_limit_mb.to_string(),
+            cache_dir: settings
+                .cache_dir
+                .as_ref()
+                .map(|dir| dir.display().to_string())
+                .unwrap_or_default(),
+            backend,
+            location,
+            scroll: scrollable::State::new(),
+            theme_picker: pick_list::State::default(),
+            backend_picker: pick_list::State::default(),
+            inputs: Default::default(),
+            save: button::State::new(),
+            cancel: button::State::new(),
+        }
+    }
+
+    pub fn update(&mut self, message: SettingsMessage) {
+        match message {
+            SettingsMessage::ThemePicked(theme) => self.theme = theme,
+            SettingsMessage::BackendPicked(backend) => self.backend = backend,
+            SettingsMessage::FieldChanged(field, value) => *self.field_mut(field) = value,
+        }
+    }
+
+    fn field_mut(&mut self, field: SettingsField) -> &mut String {
+        match field {
+            SettingsField::SyncInterval => &mut self.sync_interval,
+            SettingsField::ConcurrentDownloads => &mut self.concurrent_downloads,
+            SettingsField::RequestsPerSecond => &mut self.requests_per_second,
+            SettingsField::DefaultServings => &mut self.default_servings,
+            SettingsField::PhotoLimit => &mut self.photo_limit,
+            SettingsField::CacheDir => &mut self.cache_dir,
+            SettingsField::Location => &mut self.location,
+        }
+    }
+
+    /// `settings` with the changes made here, or what's wrong with them
+    pub fn to_settings(&self, settings: &Settings) -> Result<Settings, String> {
+        fn number<T: FromStr>(text: &str, what: &str) -> Result<T, String> {
+            text.trim()
+                .parse()
+                .map_err(|_| format!("{} has to be a whole number", what))
+        }
+
+        let mut changed = settings.clone();
+        changed.theme = Some(self.theme.clone());
+        changed.sync.interval_minutes = number(&self.sync_interval, "The sync interval")?;
+        changed.sync.concurrent_downloads =
+            number(&self.concurrent_downloads, "Downloads at once")?;
+        if changed.sync.concurrent_downloads == 0 {
+            return Err("At least one download has to run at once".into());
+        }
+        changed.sync.requests_per_second =
+            number(&self.requests_per_second, "Downloads per second")?;
+        changed.default_servings = self.default_servings.trim().into();
+        changed.cache.photo_limit_mb = number(&self.photo_limit, "The photo cache limit")?;
+        changed.cache_dir = Some(self.cache_dir.trim())
+            .filter(|dir| !dir.is_empty())
+            .map(path::PathBuf::from);
+
+        let backend = self.backend.config(&self.location).ok_or_else(|| {
+            let (label, _) = self.backend.location_label().unwrap_or_default();
+            format!("{} can't be empty", label)
+        })?;
+        let current = settings.current_profile().id;
+        match changed
+            .profiles
+            .iter_mut()
+            .find(|profile| profile.id == current)
+        {
+            Some(profile) => profile.backend = backend,
+            None => {
+                let mut profile = settings.current_profile();
+                profile.backend = backend;
+                changed.profiles.push(profile);
+            }
+        }
+        Ok(changed)
+    }
+
+    pub fn view(
+        &mut self,
+        pane: pane_grid::Pane,
+        settings: &Settings,
+        theme: Theme,
+    ) -> Element<'_, Message> {
+        let problem = self.to_settings(settings).err();
+        let SettingsForm {
+            theme: theme_name,
+            themes,
+            sync_interval,
+            concurrent_downloads,
+            requests_per_second,
+            default_servings,
+            photo_limit,
+            cache_dir,
+            backend,
+            location,
+            scroll,
+            theme_picker,
+            backend_picker,
+            inputs,
+            save,
+            cancel,
+        } = self;
+        let [sync_interval_input, concurrent_downloads_input, requests_per_second_input, default_servings_input, photo_limit_input, cache_dir_input, location_input] =
+            inputs;
+
+        let row = |label: &str| {
+            Row::new()
+                .spacing(10)
+                .align_items(Align::Center)
+                .push(Text::new(label).width(Length::Units(LABEL_WIDTH)))
+        };
+        let input = |state, placeholder, value: &str, field| {
+            TextInput::new(state, placeholder, value, move |value| {
+                Message::SettingsForm(pane, SettingsMessage::FieldChanged(field, value))
+            })
+            .style(theme)
+            .padding(5)
+        };
+        let note = |text: &str| Text::new(text).size(16);
+
+        let mut content = Scrollable::new(scroll)
+            .style(theme)
+            .padding(10)
+            .spacing(15)
+            .push(Text::new("Settings").size(28))
+            .push(Text::new("Appearance").size(22))
+            .push(
+                row("Theme").push(
+                    PickList::new(
+                        theme_picker,
+                        themes.clone(),
+                        Some(theme_name.clone()),
+                        move |theme| {
+                            Message::SettingsForm(pane, SettingsMessage::ThemePicked(theme))
+                        },
+                    )
+                    .style(theme),
+                ),
+            )
+            .push(note("More themes can be added in themes.toml."))
+            .push(Text::new("Sync").size(22))
+            .push(
+                row("Sync with").push(
+                    PickList::new(
+                        backend_picker,
+                        &BackendKind::ALL[..],
+                        Some(*backend),
+                        move |backend| {
+                            Message::SettingsForm(pane, SettingsMessage::BackendPicked(backend))
+                        },
+                    )
+                    .style(theme),
+                ),
+            );
+        if let Some((label, placeholder)) = backend.location_label() {
+            content = content.push(row(label).push(input(
+                location_input,
+                placeholder,
+                location,
+                SettingsField::Location,
+            )));
+        }
+        content = content
+            .push(note(
+                "Changing where this profile syncs logs it out and starts its recipes over.",
+            ))
+            .push(row("Sync every (minutes)").push(input(
+                sync_interval_input,
+                "0 to only sync when asked",
+                sync_interval,
+                SettingsField::SyncInterval,
+            )))
+            .push(row("Downloads at once").push(input(
+                concurrent_downloads_input,
+                "",
+                concurrent_downloads,
+                SettingsField::ConcurrentDownloads,
+            )))
+            .push(row("Downloads per second").push(input(
+                requests_per_second_input,
+                "0 for no limit",
+                requests_per_second,
+                SettingsField::RequestsPerSecond,
+            )))
+            .push(Text::new("New recipes").size(22))
+            .push(row("Servings").push(input(
+                default_servings_input,
+                "4 servings",
+                default_servings,
+                SettingsField::DefaultServings,
+            )))
+            .push(Text::new("Storage").size(22))
+            .push(row("Photo cache limit (MB)").push(input(
+                photo_limit_input,
+                "0 for no limit",
+                photo_limit,
+                SettingsField::PhotoLimit,
+            )))
+            .push(row("Cache folder").push(input(
+                cache_dir_input,
+                "The usual place",
+                cache_dir,
+                SettingsField::CacheDir,
+            )))
+            .push(note(
+                "Downloaded photos move to a new cache folder straight away.",
+            ));
+
+        if let Some(problem) = &problem {
+            content = content.push(Text::new(problem));
+        }
+        let mut save_button = Button::new(save, Text::new("Save")).style(theme);
+        if problem.is_none() {
+            save_button = save_button.on_press(Message::SettingsSaved(pane));
+        }
+        content
+            .push(
+                Row::new().spacing(10).push(save_button).push(
+                    Button::new(cancel, Text::new("Cancel"))
+                        .style(theme)
+                        .on_press(Message::Close(pane)),
+                ),
+            )
+            .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::backend::BackendConfig;
+
+    #[test]
+    fn only_sensible_settings_are_saved() {
+        let settings = Settings::default();
+        let mut form = SettingsForm::new(&settings, &theme::built_in());
+        assert_eq!(
+            form.to_settings(&settings).unwrap().theme,
+            Some("Light".into())
+        );
+
+        form.update(SettingsMessage::ThemePicked("Dark".into()));
+        form.update(SettingsMessage::FieldChanged(
+            SettingsField::SyncInterval,
+            " 15 ".into(),
+        ));
+        form.update(SettingsMessage::FieldChanged(
+            SettingsField::CacheDir,
+            "/tmp/ancho".into(),
+        ));
+        form.update(SettingsMessage::BackendPicked(BackendKind::Server));
+        assert!(form.to_settings(&settings).is_err());
+
+        form.update(SettingsMessage::FieldChanged(
+            SettingsField::Location,
+            "http://kitchen.local/api/v2".into(),
+        ));
+        let changed = form.to_settings(&settings).unwrap();
+        assert_eq!(changed.theme, Some("Dark".into()));
+        assert_eq!(changed.sync.interval_minutes, 15);
+        assert_eq!(changed.cache_dir, Some(path::PathBuf::from("/tmp/ancho")));
+        assert_eq!(
+            changed.current_profile().backend,
+            BackendConfig::Server {
+                url: "http://kitchen.local/api/v2".into()
+            }
+        );
+
+        form.update(SettingsMessage::FieldChanged(
+            SettingsField::ConcurrentDownloads,
+            "lots".into(),
+        ));
+        assert!(form.to_settings(&settings).is_err());
+    }
+}
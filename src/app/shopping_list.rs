@@ -0,0 +1,64 @@
+use crate::app::theme::Theme;
+use crate::app::Message;
+
+use iced::{button, scrollable, Button, Column, Element, Length, Scrollable, Text};
+use paprika_api::api::Recipe;
+use std::sync::{Arc, Mutex};
+
+/// The ingredients of every recipe on the shopping list, grouped by recipe
+pub struct ShoppingList {
+    recipes: Arc<Mutex<Vec<Recipe>>>,
+    scroll: scrollable::State,
+    recipe_buttons: Vec<button::State>,
+}
+
+impl ShoppingList {
+    pub fn new(recipes: Arc<Mutex<Vec<Recipe>>>) -> Self {
+        Self {
+            recipes,
+            scroll: scrollable::State::new(),
+            recipe_buttons: Vec::new(),
+        }
+    }
+
+    pub fn view(&mut self, theme: Theme) -> Element<'_, Message> {
+        let recipes = self.recipes.clone();
+        let recipes = recipes.lock().unwrap();
+        let listed: Vec<&Recipe> = recipes
+            .iter()
+            .filter(|recipe| recipe.on_grocery_list && !recipe.in_trash)
+            .collect();
+
+        let mut content = Scrollable::new(&mut self.scroll)
+            .style(theme)
+            .width(Length::Fill)
+            .padding(10)
+            .spacing(15);
+
+        if listed.is_empty() {
+            content = content.push(Text::new(
+                "Nothing to buy. Add recipes to the shopping list from their page.",
+            ));
+        }
+
+        self.recipe_buttons
+            .resize_with(listed.len(), button::State::new);
+        for (recipe, state) in listed.iter().zip(self.recipe_buttons.iter_mut()) {
+            let ingredients = recipe
+                .ingredients
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .fold(
+                    Column::new().spacing(5).push(
+                        Button::new(state, Text::new(&recipe.name).size(22))
+                            .style(theme)
+                            .on_press(Message::RecipeClicked(recipe.uid.clone())),
+                    ),
+                    |column, line| column.push(Text::new(format!("☐ {}", line.trim()))),
+                );
+            content = content.push(ingredients);
+        }
+
+        content.into()
+    }
+}
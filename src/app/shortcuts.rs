@@ -0,0 +1,368 @@
+//! Keyboard shortcuts for the things people do most, configurable from `settings.toml`:
+//!
+//! ```toml
+//! [shortcuts]
+//! new_recipe = "Ctrl+N"
+//! sync_now = ""          # no shortcut
+//! ```
+//!
+//! "Ctrl" is the command key on macOS.
+
+use iced::keyboard::{KeyCode, Modifiers};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+
+/// Something a shortcut or the command palette can do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    CommandPalette,
+    NewRecipe,
+    FocusSearch,
+    SyncNow,
+    NextRecipe,
+    PreviousRecipe,
+    EditRecipe,
+    SplitRight,
+    SplitDown,
+    ClosePane,
+    ManageCategories,
+    ShowShoppingList,
+    ShowTrash,
+}
+
+impl Action {
+    pub const ALL: [Action; 13] = [
+        Action::CommandPalette,
+        Action::NewRecipe,
+        Action::FocusSearch,
+        Action::SyncNow,
+        Action::NextRecipe,
+        Action::PreviousRecipe,
+        Action::EditRecipe,
+        Action::SplitRight,
+        Action::SplitDown,
+        Action::ClosePane,
+        Action::ManageCategories,
+        Action::ShowShoppingList,
+        Action::ShowTrash,
+    ];
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Action::CommandPalette => "Command palette",
+                Action::NewRecipe => "New recipe",
+                Action::FocusSearch => "Search recipes",
+                Action::SyncNow => "Sync now",
+                Action::NextRecipe => "Next recipe",
+                Action::PreviousRecipe => "Previous recipe",
+                Action::EditRecipe => "Edit recipe",
+                Action::SplitRight => "Split pane right",
+                Action::SplitDown => "Split pane down",
+                Action::ClosePane => "Close pane",
+                Action::ManageCategories => "Manage categories",
+                Action::ShowShoppingList => "Show shopping list",
+                Action::ShowTrash => "Show trash",
+            }
+        )
+    }
+}
+
+/// Key names as written in the settings file
+const KEY_NAMES: [(KeyCode, &str); 62] = [
+    (KeyCode::A, "A"),
+    (KeyCode::B, "B"),
+    (KeyCode::C, "C"),
+    (KeyCode::D, "D"),
+    (KeyCode::E, "E"),
+    (KeyCode::F, "F"),
+    (KeyCode::G, "G"),
+    (KeyCode::H, "H"),
+    (KeyCode::I, "I"),
+    (KeyCode::J, "J"),
+    (KeyCode::K, "K"),
+    (KeyCode::L, "L"),
+    (KeyCode::M, "M"),
+    (KeyCode::N, "N"),
+    (KeyCode::O, "O"),
+    (KeyCode::P, "P"),
+    (KeyCode::Q, "Q"),
+    (KeyCode::R, "R"),
+    (KeyCode::S, "S"),
+    (KeyCode::T, "T"),
+    (KeyCode::U, "U"),
+    (KeyCode::V, "V"),
+    (KeyCode::W, "W"),
+    (KeyCode::X, "X"),
+    (KeyCode::Y, "Y"),
+    (KeyCode::Z, "Z"),
+    (KeyCode::Key0, "0"),
+    (KeyCode::Key1, "1"),
+    (KeyCode::Key2, "2"),
+    (KeyCode::Key3, "3"),
+    (KeyCode::Key4, "4"),
+    (KeyCode::Key5, "5"),
+    (KeyCode::Key6, "6"),
+    (KeyCode::Key7, "7"),
+    (KeyCode::Key8, "8"),
+    (KeyCode::Key9, "9"),
+    (KeyCode::F1, "F1"),
+    (KeyCode::F2, "F2"),
+    (KeyCode::F3, "F3"),
+    (KeyCode::F4, "F4"),
+    (KeyCode::F5, "F5"),
+    (KeyCode::F6, "F6"),
+    (KeyCode::F7, "F7"),
+    (KeyCode::F8, "F8"),
+    (KeyCode::F9, "F9"),
+    (KeyCode::F10, "F10"),
+    (KeyCode::F11, "F11"),
+    (KeyCode::F12, "F12"),
+    (KeyCode::Up, "Up"),
+    (KeyCode::Down, "Down"),
+    (KeyCode::Left, "Left"),
+    (KeyCode::Right, "Right"),
+    (KeyCode::PageUp, "PageUp"),
+    (KeyCode::PageDown, "PageDown"),
+    (KeyCode::Home, "Home"),
+    (KeyCode::End, "End"),
+    (KeyCode::Enter, "Enter"),
+    (KeyCode::Escape, "Escape"),
+    (KeyCode::Tab, "Tab"),
+    (KeyCode::Space, "Space"),
+    (KeyCode::Delete, "Delete"),
+    (KeyCode::Slash, "/"),
+];
+
+/// A key and the modifiers held with it, or nothing for an action without a shortcut
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Shortcut {
+    key: Option<KeyCode>,
+    command: bool,
+    shift: bool,
+    alt: bool,
+}
+
+impl Shortcut {
+    pub fn matches(&self, key: KeyCode, modifiers: Modifiers) -> bool {
+        self.key == Some(key)
+            && self.command == modifiers.is_command_pressed()
+            && self.shift == modifiers.shift
+            && self.alt == modifiers.alt
+    }
+}
+
+impl TryFrom<String> for Shortcut {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        let mut shortcut = Shortcut::default();
+        if text.trim().is_empty() {
+            return Ok(shortcut);
+        }
+
+        for part in text.split('+').map(str::trim) {
+            match part.to_lowercase().as_str() {
+                "ctrl" | "cmd" | "command" => shortcut.command = true,
+                "shift" => shortcut.shift = true,
+                "alt" | "option" => shortcut.alt = true,
+                _ if shortcut.key.is_some() => {
+                    return Err(format!("{:?} has more than one key", text))
+                }
+                _ => {
+                    let key = KEY_NAMES
+                        .iter()
+                        .find(|(_, name)| name.eq_ignore_ascii_case(part))
+                        .map(|(key, _)| *key)
+                        .ok_or_else(|| format!("{:?} isn't a key Ancho knows", part))?;
+                    shortcut.key = Some(key);
+                }
+            }
+        }
+
+        if shortcut.key.is_none() {
+            return Err(format!("{:?} has no key", text));
+        }
+        Ok(shortcut)
+    }
+}
+
+impl From<Shortcut> for String {
+    fn from(shortcut: Shortcut) -> Self {
+        shortcut.to_string()
+    }
+}
+
+impl fmt::Display for Shortcut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match KEY_NAMES.iter().find(|(key, _)| Some(*key) == self.key) {
+            Some((_, name)) => name,
+            None => return Ok(()),
+        };
+        let modifiers = [
+            (self.command, "Ctrl+"),
+            (self.shift, "Shift+"),
+            (self.alt, "Alt+"),
+        ];
+        for (_, modifier) in modifiers.iter().filter(|(held, _)| *held) {
+            write!(f, "{}", modifier)?;
+        }
+        write!(f, "{}", name)
+    }
+}
+
+/// Which shortcut does what. A shortcut that can't be read keeps its default rather than
+/// failing the whole settings file, and is listed in `problems`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "BTreeMap<String, String>")]
+pub struct Shortcuts {
+    pub command_palette: Shortcut,
+    pub new_recipe: Shortcut,
+    pub focus_search: Shortcut,
+    pub sync_now: Shortcut,
+    pub next_recipe: Shortcut,
+    pub previous_recipe: Shortcut,
+    pub edit_recipe: Shortcut,
+    pub split_right: Shortcut,
+    pub split_down: Shortcut,
+    pub close_pane: Shortcut,
+    pub manage_categories: Shortcut,
+    pub show_shopping_list: Shortcut,
+    pub show_trash: Shortcut,
+    /// What was wrong with the shortcuts as written
+    #[serde(skip)]
+    pub problems: Vec<String>,
+}
+
+impl Default for Shortcuts {
+    fn default() -> Self {
+        let shortcut = |text: &str| Shortcut::try_from(text.to_string()).unwrap();
+        Self {
+            command_palette: shortcut("Ctrl+K"),
+            new_recipe: shortcut("Ctrl+N"),
+            focus_search: shortcut("Ctrl+F"),
+            sync_now: shortcut("Ctrl+R"),
+            next_recipe: shortcut("Alt+Down"),
+            previous_recipe: shortcut("Alt+Up"),
+            edit_recipe: shortcut("Ctrl+E"),
+            split_right: shortcut("Ctrl+D"),
+            split_down: shortcut("Ctrl+Shift+D"),
+            close_pane: shortcut("Ctrl+W"),
+            manage_categories: Shortcut::default(),
+            show_shopping_list: shortcut("Ctrl+L"),
+            show_trash: Shortcut::default(),
+            problems: Vec::new(),
+        }
+    }
+}
+
+impl From<BTreeMap<String, String>> for Shortcuts {
+    fn from(mut written: BTreeMap<String, String>) -> Self {
+        let mut shortcuts = Shortcuts::default();
+        let mut problems = Vec::new();
+        let fields = [
+            ("command_palette", &mut shortcuts.command_palette),
+            ("new_recipe", &mut shortcuts.new_recipe),
+            ("focus_search", &mut shortcuts.focus_search),
+            ("sync_now", &mut shortcuts.sync_now),
+            ("next_recipe", &mut shortcuts.next_recipe),
+            ("previous_recipe", &mut shortcuts.previous_recipe),
+            ("edit_recipe", &mut shortcuts.edit_recipe),
+            ("split_right", &mut shortcuts.split_right),
+            ("split_down", &mut shortcuts.split_down),
+            ("close_pane", &mut shortcuts.close_pane),
+            ("manage_categories", &mut shortcuts.manage_categories),
+            ("show_shopping_list", &mut shortcuts.show_shopping_list),
+            ("show_trash", &mut shortcuts.show_trash),
+        ];
+        for (name, field) in fields {
+            if let Some(text) = written.remove(name) {
+                match Shortcut::try_from(text) {
+                    Ok(shortcut) => *field = shortcut,
+                    Err(e) => problems.push(format!("ignoring the {} shortcut: {}", name, e)),
+                }
+            }
+        }
+        shortcuts.problems = problems;
+        shortcuts
+    }
+}
+
+impl Shortcuts {
+    pub fn shortcut(&self, action: Action) -> Shortcut {
+        match action {
+            Action::CommandPalette => self.command_palette,
+            Action::NewRecipe => self.new_recipe,
+            Action::FocusSearch => self.focus_search,
+            Action::SyncNow => self.sync_now,
+            Action::NextRecipe => self.next_recipe,
+            Action::PreviousRecipe => self.previous_recipe,
+            Action::EditRecipe => self.edit_recipe,
+            Action::SplitRight => self.split_right,
+            Action::SplitDown => self.split_down,
+            Action::ClosePane => self.close_pane,
+            Action::ManageCategories => self.manage_categories,
+            Action::ShowShoppingList => self.show_shopping_list,
+            Action::ShowTrash => self.show_trash,
+        }
+    }
+
+    /// The action bound to a key press, if any
+    pub fn action(&self, key: KeyCode, modifiers: Modifiers) -> Option<Action> {
+        Action::ALL
+            .iter()
+            .copied()
+            .find(|action| self.shortcut(*action).matches(key, modifiers))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shortcuts_read_the_way_they_are_written() {
+        let shortcut = Shortcut::try_from("ctrl + shift + d".to_string()).unwrap();
+        assert_eq!(shortcut.to_string(), "Ctrl+Shift+D");
+        assert_eq!(Shortcut::try_from(String::new()), Ok(Shortcut::default()));
+        assert!(Shortcut::try_from("Ctrl+Hyper".to_string()).is_err());
+        assert!(Shortcut::try_from("Ctrl+A+B".to_string()).is_err());
+        assert!(Shortcut::try_from("Shift".to_string()).is_err());
+
+        let shortcuts = Shortcuts::default();
+        let ctrl = Modifiers {
+            control: true,
+            logo: true,
+            ..Modifiers::default()
+        };
+        assert_eq!(shortcuts.action(KeyCode::N, ctrl), Some(Action::NewRecipe));
+        assert_eq!(shortcuts.action(KeyCode::N, Modifiers::default()), None);
+        let ctrl_shift = Modifiers {
+            shift: true,
+            ..ctrl
+        };
+        assert_eq!(
+            shortcuts.action(KeyCode::D, ctrl_shift),
+            Some(Action::SplitDown)
+        );
+    }
+
+    #[test]
+    fn a_bad_shortcut_only_loses_itself() {
+        let written = [("new_recipe", "Ctrl+Hyper"), ("show_trash", "F9")]
+            .iter()
+            .map(|(name, text)| (name.to_string(), text.to_string()))
+            .collect::<BTreeMap<_, _>>();
+        let shortcuts = Shortcuts::from(written);
+        assert_eq!(shortcuts.new_recipe, Shortcuts::default().new_recipe);
+        assert_eq!(shortcuts.show_trash.to_string(), "F9");
+        assert_eq!(shortcuts.problems.len(), 1);
+    }
+}
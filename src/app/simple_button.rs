@@ -1,3 +1,4 @@
+use crate::app::theme::Theme;
 use crate::app::Message;
 
 use iced::{button, Button, Text};
@@ -12,14 +13,15 @@ impl SimpleButton {
     pub fn new(text: String, on_pressed: Message) -> Self {
         Self {
             state: button::State::new(),
-            text: text.into(),
-            on_pressed: on_pressed,
+            text,
+            on_pressed,
         }
     }
 
-    pub fn to_button(&mut self) -> button::Button<Message> {
+    #[allow(clippy::wrong_self_convention)]
+    pub fn to_button(&mut self, theme: Theme) -> button::Button<'_, Message> {
         Button::new(&mut self.state, Text::new(&self.text))
+            .style(theme)
             .on_press(self.on_pressed.clone())
-            .into()
     }
 }
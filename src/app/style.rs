@@ -1,22 +1,230 @@
-#[allow(unused)]
-use iced::{button, container, Background, Color};
+use crate::app::theme::Theme;
+
+use iced::{button, checkbox, container, pick_list, scrollable, text_input, Background, Color};
+
+/// `color` at `alpha` opacity, for muted text and selections
+fn faded(color: Color, alpha: f32) -> Color {
+    Color {
+        a: color.a * alpha,
+        ..color
+    }
+}
+
+pub struct StatusBar(pub Theme);
+
+impl container::StyleSheet for StatusBar {
+    fn style(&self) -> container::Style {
+        container::Style {
+            text_color: Some(self.0.accent_text),
+            background: Some(Background::Color(self.0.error)),
+            ..Default::default()
+        }
+    }
+}
 
 pub struct Pane {
+    pub theme: Theme,
     pub is_nav_pane: bool,
+    pub is_focused: bool,
 }
 
 impl container::StyleSheet for Pane {
     fn style(&self) -> container::Style {
+        let background = if self.is_nav_pane {
+            self.theme.surface
+        } else {
+            self.theme.background
+        };
         container::Style {
-            background: Some(Background::Color(Color::new(0.1, 0.1, 0.1, 0.1))),
+            text_color: Some(self.theme.text),
+            background: Some(Background::Color(background)),
             border_width: 2.0,
-            border_color: if self.is_nav_pane {
-                //Color::BLACK
-                Color::from_rgb(0.7, 0.7, 0.7)
+            border_color: if self.is_focused {
+                self.theme.accent
             } else {
-                Color::from_rgb(0.7, 0.7, 0.7)
+                self.theme.border
             },
             ..Default::default()
         }
     }
 }
+
+pub struct TitleBar {
+    pub theme: Theme,
+    pub is_focused: bool,
+}
+
+impl container::StyleSheet for TitleBar {
+    fn style(&self) -> container::Style {
+        let (text, background) = if self.is_focused {
+            (self.theme.accent_text, self.theme.accent)
+        } else {
+            (self.theme.text, self.theme.surface)
+        };
+        container::Style {
+            text_color: Some(text),
+            background: Some(Background::Color(background)),
+            ..Default::default()
+        }
+    }
+}
+
+/// A box set apart from the panes, like the command palette
+impl container::StyleSheet for Theme {
+    fn style(&self) -> container::Style {
+        container::Style {
+            text_color: Some(self.text),
+            background: Some(Background::Color(self.surface)),
+            border_width: 1.0,
+            border_color: self.border,
+            ..Default::default()
+        }
+    }
+}
+
+impl button::StyleSheet for Theme {
+    fn active(&self) -> button::Style {
+        button::Style {
+            background: Some(Background::Color(self.surface)),
+            border_radius: 3.0,
+            border_width: 1.0,
+            border_color: self.border,
+            text_color: self.text,
+            ..Default::default()
+        }
+    }
+
+    fn hovered(&self) -> button::Style {
+        button::Style {
+            border_color: self.accent,
+            ..self.active()
+        }
+    }
+
+    fn pressed(&self) -> button::Style {
+        button::Style {
+            background: Some(Background::Color(self.accent)),
+            text_color: self.accent_text,
+            ..self.hovered()
+        }
+    }
+
+    fn disabled(&self) -> button::Style {
+        let active = self.active();
+        button::Style {
+            border_color: faded(self.border, 0.5),
+            text_color: faded(self.text, 0.5),
+            ..active
+        }
+    }
+}
+
+impl text_input::StyleSheet for Theme {
+    fn active(&self) -> text_input::Style {
+        text_input::Style {
+            background: Background::Color(self.background),
+            border_radius: 3.0,
+            border_width: 1.0,
+            border_color: self.border,
+        }
+    }
+
+    fn focused(&self) -> text_input::Style {
+        text_input::Style {
+            border_width: 2.0,
+            border_color: self.accent,
+            ..text_input::StyleSheet::active(self)
+        }
+    }
+
+    fn placeholder_color(&self) -> Color {
+        faded(self.text, 0.5)
+    }
+
+    fn value_color(&self) -> Color {
+        self.text
+    }
+
+    fn selection_color(&self) -> Color {
+        faded(self.accent, 0.4)
+    }
+}
+
+impl scrollable::StyleSheet for Theme {
+    fn active(&self) -> scrollable::Scrollbar {
+        scrollable::Scrollbar {
+            background: None,
+            border_radius: 3.0,
+            border_width: 0.0,
+            border_color: Color::TRANSPARENT,
+            scroller: scrollable::Scroller {
+                color: self.border,
+                border_radius: 3.0,
+                border_width: 0.0,
+                border_color: Color::TRANSPARENT,
+            },
+        }
+    }
+
+    fn hovered(&self) -> scrollable::Scrollbar {
+        let active = scrollable::StyleSheet::active(self);
+        scrollable::Scrollbar {
+            background: Some(Background::Color(faded(self.border, 0.3))),
+            scroller: scrollable::Scroller {
+                color: self.accent,
+                ..active.scroller
+            },
+            ..active
+        }
+    }
+}
+
+impl pick_list::StyleSheet for Theme {
+    fn menu(&self) -> pick_list::Menu {
+        pick_list::Menu {
+            text_color: self.text,
+            background: Background::Color(self.surface),
+            border_width: 1.0,
+            border_color: self.border,
+            selected_text_color: self.accent_text,
+            selected_background: Background::Color(self.accent),
+        }
+    }
+
+    fn active(&self) -> pick_list::Style {
+        pick_list::Style {
+            text_color: self.text,
+            background: Background::Color(self.surface),
+            border_radius: 3.0,
+            border_width: 1.0,
+            border_color: self.border,
+            icon_size: 0.7,
+        }
+    }
+
+    fn hovered(&self) -> pick_list::Style {
+        pick_list::Style {
+            border_color: self.accent,
+            ..pick_list::StyleSheet::active(self)
+        }
+    }
+}
+
+impl checkbox::StyleSheet for Theme {
+    fn active(&self, _is_checked: bool) -> checkbox::Style {
+        checkbox::Style {
+            background: Background::Color(self.background),
+            checkmark_color: self.accent,
+            border_radius: 3.0,
+            border_width: 1.0,
+            border_color: self.border,
+        }
+    }
+
+    fn hovered(&self, is_checked: bool) -> checkbox::Style {
+        checkbox::Style {
+            border_color: self.accent,
+            ..checkbox::StyleSheet::active(self, is_checked)
+        }
+    }
+}
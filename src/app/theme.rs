@@ -0,0 +1,246 @@
+//! Color schemes. Light, dark and high-contrast themes are built in, and more can be added in
+//! `themes.toml` next to `settings.toml`, starting from a built-in one and changing what differs:
+//!
+//! ```toml
+//! [[theme]]
+//! name = "Kitchen"
+//! base = "High contrast"
+//! accent = "#ff8800"
+//! ```
+//!
+//! A theme named after a built-in one replaces it. The colors are `background`, `surface` (the
+//! nav pane, title bars and buttons), `text`, `border`, `accent` (focus and selection),
+//! `accent_text` (text on the accent and error colors) and `error`.
+
+use crate::app::error::{Error, Result};
+
+use iced::Color;
+use serde::Deserialize;
+use std::convert::TryFrom;
+use std::{fs, path};
+
+const THEMES_FILE: &str = "themes.toml";
+
+pub fn themes_file(config_dir: &path::Path) -> path::PathBuf {
+    config_dir.join(THEMES_FILE)
+}
+
+/// The colors everything is drawn in. `style` turns them into stylesheets.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    pub background: Color,
+    pub surface: Color,
+    pub text: Color,
+    pub border: Color,
+    pub accent: Color,
+    pub accent_text: Color,
+    pub error: Color,
+}
+
+impl Theme {
+    pub fn light() -> Self {
+        Self {
+            background: Color::from_rgb8(0xfa, 0xfa, 0xf7),
+            surface: Color::from_rgb8(0xe9, 0xe8, 0xe2),
+            text: Color::from_rgb8(0x1c, 0x1c, 0x1c),
+            border: Color::from_rgb8(0xa8, 0xa6, 0x9c),
+            accent: Color::from_rgb8(0x2a, 0x62, 0xa8),
+            accent_text: Color::WHITE,
+            error: Color::from_rgb8(0xb0, 0x2a, 0x2a),
+        }
+    }
+
+    pub fn dark() -> Self {
+        Self {
+            background: Color::from_rgb8(0x1d, 0x1e, 0x21),
+            surface: Color::from_rgb8(0x2c, 0x2e, 0x33),
+            text: Color::from_rgb8(0xe8, 0xe6, 0xe1),
+            border: Color::from_rgb8(0x55, 0x58, 0x60),
+            accent: Color::from_rgb8(0x3f, 0x7f, 0xcc),
+            accent_text: Color::WHITE,
+            error: Color::from_rgb8(0xc0, 0x3c, 0x3c),
+        }
+    }
+
+    pub fn high_contrast() -> Self {
+        Self {
+            background: Color::BLACK,
+            surface: Color::BLACK,
+            text: Color::WHITE,
+            border: Color::WHITE,
+            accent: Color::from_rgb8(0xff, 0xd4, 0x00),
+            accent_text: Color::BLACK,
+            error: Color::from_rgb8(0xff, 0x5c, 0x5c),
+        }
+    }
+}
+
+/// A theme and the name it's picked by
+#[derive(Debug, Clone, PartialEq)]
+pub struct NamedTheme {
+    pub name: String,
+    pub theme: Theme,
+}
+
+/// The built-in themes, the default first
+pub fn built_in() -> Vec<NamedTheme> {
+    vec![
+        NamedTheme {
+            name: "Light".into(),
+            theme: Theme::light(),
+        },
+        NamedTheme {
+            name: "Dark".into(),
+            theme: Theme::dark(),
+        },
+        NamedTheme {
+            name: "High contrast".into(),
+            theme: Theme::high_contrast(),
+        },
+    ]
+}
+
+/// The built-in themes followed by any in the themes file at `path`
+pub fn load(path: &path::Path) -> Result<Vec<NamedTheme>> {
+    let mut themes = built_in();
+    if !path.is_file() {
+        return Ok(themes);
+    }
+
+    let contents = fs::read_to_string(path)
+        .map_err(|e| Error::Settings(format!("{}: {}", path.display(), e)))?;
+    let file: ThemesFile = toml::from_str(&contents)
+        .map_err(|e| Error::Settings(format!("{}: {}", path.display(), e)))?;
+    for user_theme in file.themes {
+        let theme = user_theme.resolve(&themes);
+        match themes
+            .iter_mut()
+            .find(|named| named.name.eq_ignore_ascii_case(&user_theme.name))
+        {
+            Some(named) => named.theme = theme,
+            None => themes.push(NamedTheme {
+                name: user_theme.name,
+                theme,
+            }),
+        }
+    }
+    Ok(themes)
+}
+
+/// The theme called `name`, or the default if there's no such theme
+pub fn find<'a>(themes: &'a [NamedTheme], name: Option<&str>) -> Option<&'a NamedTheme> {
+    name.and_then(|name| {
+        themes
+            .iter()
+            .find(|named| named.name.eq_ignore_ascii_case(name))
+    })
+    .or_else(|| themes.first())
+}
+
+#[derive(Deserialize)]
+struct ThemesFile {
+    #[serde(default, rename = "theme")]
+    themes: Vec<UserTheme>,
+}
+
+/// A theme from the themes file. Colors left out come from `base`, or else from the built-in
+/// theme it's named after, or else from the default.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct UserTheme {
+    name: String,
+    base: Option<String>,
+    background: Option<HexColor>,
+    surface: Option<HexColor>,
+    text: Option<HexColor>,
+    border: Option<HexColor>,
+    accent: Option<HexColor>,
+    accent_text: Option<HexColor>,
+    error: Option<HexColor>,
+}
+
+impl UserTheme {
+    fn resolve(&self, themes: &[NamedTheme]) -> Theme {
+        let base = find(themes, Some(self.base.as_deref().unwrap_or(&self.name)))
+            .map_or_else(Theme::light, |named| named.theme);
+        let color = |color: Option<HexColor>, base: Color| color.map_or(base, |color| color.0);
+        Theme {
+            background: color(self.background, base.background),
+            surface: color(self.surface, base.surface),
+            text: color(self.text, base.text),
+            border: color(self.border, base.border),
+            accent: color(self.accent, base.accent),
+            accent_text: color(self.accent_text, base.accent_text),
+            error: color(self.error, base.error),
+        }
+    }
+}
+
+/// A color written as `#rrggbb` or `#rrggbbaa`
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(try_from = "String")]
+struct HexColor(Color);
+
+impl TryFrom<String> for HexColor {
+    type Error = String;
+
+    fn try_from(text: String) -> std::result::Result<Self, Self::Error> {
+        let invalid = || format!("{:?} isn't a color like \"#336699\"", text);
+        let hex = text.trim().strip_prefix('#').ok_or_else(invalid)?;
+        if !(hex.len() == 6 || hex.len() == 8) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap();
+        let mut color = Color::from_rgb8(channel(0), channel(2), channel(4));
+        if hex.len() == 8 {
+            color.a = f32::from(channel(6)) / 255.0;
+        }
+        Ok(HexColor(color))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn user_themes_start_from_their_base() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = themes_file(dir.path());
+        assert_eq!(load(&path).unwrap(), built_in());
+
+        fs::write(
+            &path,
+            r##"
+            [[theme]]
+            name = "Kitchen"
+            base = "high contrast"
+            accent = "#ff8800"
+
+            [[theme]]
+            name = "Dark"
+            background = "#00000080"
+            "##,
+        )
+        .unwrap();
+        let themes = load(&path).unwrap();
+        let names: Vec<&str> = themes.iter().map(|named| named.name.as_str()).collect();
+        assert_eq!(names, ["Light", "Dark", "High contrast", "Kitchen"]);
+
+        let kitchen = find(&themes, Some("kitchen")).unwrap().theme;
+        assert_eq!(kitchen.accent, Color::from_rgb8(0xff, 0x88, 0x00));
+        assert_eq!(kitchen.text, Theme::high_contrast().text);
+        // a theme named after a built-in one changes it
+        let dark = find(&themes, Some("Dark")).unwrap().theme;
+        assert_eq!(
+            dark.background,
+            Color::from_rgba(0.0, 0.0, 0.0, 128.0 / 255.0)
+        );
+        assert_eq!(dark.surface, Theme::dark().surface);
+        assert_eq!(find(&themes, Some("Missing")).unwrap().name, "Light");
+
+        fs::write(&path, "[[theme]]\nname = \"Bad\"\naccent = \"orange\"\n").unwrap();
+        assert!(load(&path).is_err());
+    }
+}
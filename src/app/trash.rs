@@ -0,0 +1,229 @@
+use crate::app::theme::Theme;
+use crate::app::Message;
+
+use iced::{button, pane_grid, scrollable, Align, Button, Element, Length, Row, Scrollable, Text};
+use paprika_api::api::Recipe;
+use std::sync::{Arc, Mutex};
+
+/// What's about to be removed from this device, once confirmed
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Doomed {
+    /// A recipe uid
+    One(String),
+    Everything,
+}
+
+#[derive(Debug, Clone)]
+pub enum TrashMessage {
+    /// A recipe uid
+    DeleteClicked(String),
+    EmptyClicked,
+    DeleteCancelled,
+}
+
+struct TrashRow {
+    open: button::State,
+    restore: button::State,
+    delete: button::State,
+}
+
+impl TrashRow {
+    fn new() -> Self {
+        Self {
+            open: button::State::new(),
+            restore: button::State::new(),
+            delete: button::State::new(),
+        }
+    }
+}
+
+/// Lists trashed recipes for restoring or removing from this device. Removing asks first.
+///
+/// Removed recipes stay in the backend's trash: Paprika's sync API can't delete a recipe
+/// outright, so other devices keep showing it in their trash, and it comes back here if the
+/// local data is cleared.
+pub struct TrashView {
+    recipes: Arc<Mutex<Vec<Recipe>>>,
+    doomed: Option<Doomed>,
+
+    scroll: scrollable::State,
+    rows: Vec<TrashRow>,
+    empty: button::State,
+    confirm: button::State,
+    cancel: button::State,
+    close: button::State,
+}
+
+impl TrashView {
+    pub fn new(recipes: Arc<Mutex<Vec<Recipe>>>) -> Self {
+        Self {
+            recipes,
+            doomed: None,
+            scroll: scrollable::State::new(),
+            rows: Vec::new(),
+            empty: button::State::new(),
+            confirm: button::State::new(),
+            cancel: button::State::new(),
+            close: button::State::new(),
+        }
+    }
+
+    pub fn update(&mut self, message: TrashMessage) {
+        match message {
+            TrashMessage::DeleteClicked(uid) => self.doomed = Some(Doomed::One(uid)),
+            TrashMessage::EmptyClicked => self.doomed = Some(Doomed::Everything),
+            TrashMessage::DeleteCancelled => self.doomed = None,
+        }
+    }
+
+    /// Uids of the recipes the confirmed deletion covers, closing the question
+    pub fn confirm(&mut self) -> Vec<String> {
+        let recipes = self.recipes.lock().unwrap();
+        let trashed = trashed(&recipes);
+        match self.doomed.take() {
+            Some(Doomed::Everything) => trashed.iter().map(|recipe| recipe.uid.clone()).collect(),
+            Some(Doomed::One(uid)) if trashed.iter().any(|recipe| recipe.uid == uid) => vec![uid],
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn view(&mut self, pane: pane_grid::Pane, theme: Theme) -> Element<'_, Message> {
+        let recipes = self.recipes.clone();
+        let recipes = recipes.lock().unwrap();
+        let trashed = trashed(&recipes);
+
+        let mut content = Scrollable::new(&mut self.scroll)
+            .style(theme)
+            .width(Length::Fill)
+            .padding(10)
+            .spacing(5)
+            .push(Text::new("Trash").size(28));
+
+        if trashed.is_empty() {
+            content = content.push(Text::new("The trash is empty."));
+        }
+
+        self.rows.resize_with(trashed.len(), TrashRow::new);
+        for (recipe, row) in trashed.iter().zip(self.rows.iter_mut()) {
+            content = content.push(
+                Row::new()
+                    .spacing(5)
+                    .align_items(Align::Center)
+                    .push(
+                        Button::new(&mut row.open, Text::new(&recipe.name))
+                            .style(theme)
+                            .width(Length::Fill)
+                            .on_press(Message::RecipeClicked(recipe.uid.clone())),
+                    )
+                    .push(
+                        Button::new(&mut row.restore, Text::new("Restore"))
+                            .style(theme)
+                            .on_press(Message::RestoreRecipeClicked(pane, recipe.uid.clone())),
+                    )
+                    .push(
+                        Button::new(&mut row.delete, Text::new("Remove"))
+                            .style(theme)
+                            .on_press(Message::Trash(
+                                pane,
+                                TrashMessage::DeleteClicked(recipe.uid.clone()),
+                            )),
+                    ),
+            );
+        }
+
+        let question = match &self.doomed {
+            Some(Doomed::Everything) => Some(match trashed.len() {
+                1 => {
+                    "Remove 1 recipe from this device? It stays in the trash elsewhere.".to_string()
+                }
+                n => format!(
+                    "Remove {} recipes from this device? They stay in the trash elsewhere.",
+                    n
+                ),
+            }),
+            Some(Doomed::One(uid)) => {
+                trashed
+                    .iter()
+                    .find(|recipe| &recipe.uid == uid)
+                    .map(|recipe| {
+                        format!(
+                            "Remove {} from this device? It stays in the trash elsewhere.",
+                            recipe.name
+                        )
+                    })
+            }
+            None => None,
+        };
+        let buttons = match question {
+            Some(question) => Row::new()
+                .spacing(10)
+                .align_items(Align::Center)
+                .push(Text::new(question))
+                .push(
+                    Button::new(&mut self.confirm, Text::new("Remove"))
+                        .style(theme)
+                        .on_press(Message::TrashDeleteConfirmed(pane)),
+                )
+                .push(
+                    Button::new(&mut self.cancel, Text::new("Cancel"))
+                        .style(theme)
+                        .on_press(Message::Trash(pane, TrashMessage::DeleteCancelled)),
+                ),
+            None => {
+                let mut empty =
+                    Button::new(&mut self.empty, Text::new("Remove all from this device"))
+                        .style(theme);
+                if !trashed.is_empty() {
+                    empty = empty.on_press(Message::Trash(pane, TrashMessage::EmptyClicked));
+                }
+                Row::new().spacing(10).push(empty).push(
+                    Button::new(&mut self.close, Text::new("Close"))
+                        .style(theme)
+                        .on_press(Message::Close(pane)),
+                )
+            }
+        };
+
+        content.push(buttons).into()
+    }
+}
+
+fn trashed(recipes: &[Recipe]) -> Vec<&Recipe> {
+    recipes.iter().filter(|recipe| recipe.in_trash).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::backend::mock_server;
+
+    #[test]
+    fn only_trashed_recipes_are_deleted() {
+        let mut recipes = mock_server::recipes();
+        recipes[0].in_trash = true;
+        recipes[1].in_trash = true;
+        recipes[2].in_trash = false;
+        let (first, second, kept) = (
+            recipes[0].uid.clone(),
+            recipes[1].uid.clone(),
+            recipes[2].uid.clone(),
+        );
+        let mut trash = TrashView::new(Arc::new(Mutex::new(recipes)));
+
+        assert!(trash.confirm().is_empty());
+
+        trash.update(TrashMessage::DeleteClicked(kept));
+        assert!(trash.confirm().is_empty());
+
+        trash.update(TrashMessage::DeleteClicked(second.clone()));
+        trash.update(TrashMessage::DeleteCancelled);
+        assert!(trash.confirm().is_empty());
+
+        trash.update(TrashMessage::DeleteClicked(second.clone()));
+        assert_eq!(trash.confirm(), vec![second.clone()]);
+
+        trash.update(TrashMessage::EmptyClicked);
+        assert_eq!(trash.confirm(), vec![first, second]);
+        assert!(trash.confirm().is_empty());
+    }
+}
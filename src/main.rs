@@ -2,11 +2,13 @@ use iced::{Application, Settings};
 
 mod app;
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let res = app::HomePage::run(Settings::default());
-    match res {
-        Ok(_) => todo!(),
-        Err(_) => todo!(),
-    }
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // closing the window goes through the app first, so it can save the layout
+    let settings = Settings {
+        window: app::window_settings(),
+        exit_on_close_request: false,
+        ..Settings::default()
+    };
+    app::HomePage::run(settings)?;
+    Ok(())
 }